@@ -1,7 +1,18 @@
-use crate::project::{Chat, HierarchicalMessage, Project};
+use crate::index::SearchIndex;
+use crate::matcher::{MatchMode, Matcher};
+use crate::project::{Chat, ChatTotalsCache, HierarchicalMessage, MessageCategory, Project};
+use crate::query::MessageQuery;
+use crate::search::SearchHit;
+use crate::stats::{ChatStats, PriceTable, TokenTotals};
+use crate::tools::ToolCallIndex;
+use crate::viewer::Viewer;
+use chrono::Utc;
+use ratatui::text::Line;
 use ratatui::widgets::ListState;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::path::PathBuf;
-use sublime_fuzzy::best_match;
+use std::time::{Duration, Instant};
 
 pub trait ListManagerTrait {
     fn move_up(&mut self, page_size: usize);
@@ -21,6 +32,7 @@ pub struct ListManager<T> {
     pub filtered_items: Vec<T>,
     pub filtered_indices: Vec<usize>, // Maps filtered position to original position
     pub state: ListState,
+    filter_active: bool, // A filter matching nothing shows an empty list
 }
 
 impl<T> Default for ListManager<T> {
@@ -36,6 +48,7 @@ impl<T> ListManager<T> {
             filtered_items: Vec::new(),
             filtered_indices: Vec::new(),
             state: ListState::default(),
+            filter_active: false,
         }
     }
 
@@ -183,8 +196,25 @@ impl<T> ListManager<T> {
         }
     }
 
+    /// Select the first visible item matching `predicate`, or the first item
+    /// when none does.
+    pub fn select_first_where<F>(&mut self, predicate: F)
+    where
+        F: Fn(&T) -> bool,
+    {
+        let index = self.active_items().iter().position(predicate);
+        let fallback = Some(0).filter(|_| !self.is_empty());
+        self.state.select(index.or(fallback));
+    }
+
+    /// Restore a previous scroll offset, clamped to the current items.
+    pub fn restore_offset(&mut self, offset: usize) {
+        let max_offset = self.len().saturating_sub(1);
+        *self.state.offset_mut() = offset.min(max_offset);
+    }
+
     pub fn active_items(&self) -> &Vec<T> {
-        if !self.filtered_items.is_empty() {
+        if self.filter_active {
             &self.filtered_items
         } else {
             &self.items
@@ -192,7 +222,7 @@ impl<T> ListManager<T> {
     }
 
     pub fn is_filtered(&self) -> bool {
-        !self.filtered_items.is_empty()
+        self.filter_active
     }
 
     pub fn original_index(&self, filtered_index: usize) -> usize {
@@ -260,6 +290,41 @@ pub enum Screen {
     Projects,
     Chats,
     Messages,
+    SearchResults,
+}
+
+/// Pane of the messages screen that receives navigation keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    List,
+    Details,
+}
+
+/// Which sidechain (subagent) messages the messages screen shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SidechainFilter {
+    All,
+    Hide,
+    Only,
+}
+
+impl SidechainFilter {
+    pub fn next(self) -> Self {
+        match self {
+            SidechainFilter::All => SidechainFilter::Hide,
+            SidechainFilter::Hide => SidechainFilter::Only,
+            SidechainFilter::Only => SidechainFilter::All,
+        }
+    }
+
+    fn shows(self, message: &HierarchicalMessage) -> bool {
+        let is_sidechain = message.message.is_sidechain == Some(true);
+        match self {
+            SidechainFilter::All => true,
+            SidechainFilter::Hide => !is_sidechain,
+            SidechainFilter::Only => is_sidechain,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -273,12 +338,65 @@ pub struct App {
     pub should_quit: bool,
     pub search_mode: bool,
     pub search_query: String,
+    pub match_mode: MatchMode,
+    pub search_error: Option<String>, // Why the query can't be matched
+    pub find_mode: bool,              // Typing a query that selects matches without filtering
+    pub find_query: String,
+    pub find_matches: Vec<usize>, // Original indices of the messages found, in order
     pub current_project: Option<Project>,
     pub current_chat: Option<Chat>,
+    pub price_table: PriceTable,
+    pub show_stats: bool,
+    pub chat_stats: ChatStats,
+    pub search_results: ListManager<SearchHit>,
+    pub content_search_mode: bool,
+    pub content_search_query: String,
+    pub content_search_scope: Option<String>, // Project name, or None for all projects
+    pub opened_from_search: bool,
+    pub index_path: Option<PathBuf>, // None disables the on-disk search index
+    pub search_index: Option<SearchIndex>,
+    pub totals_cache_path: Option<PathBuf>, // None keeps chat totals in memory only
+    pub totals_cache: ChatTotalsCache,
+    pub status_message: Option<String>, // Shown in the status bar until the next key press
+    pub follow_mode: bool,
+    pub follow_pinned: bool,
+    pub chat_file_path: Option<PathBuf>,
+    pub chat_file_offset: u64, // Bytes of the chat file already loaded
+    pub refresh_interval: Option<Duration>, // None disables automatic refresh
+    pub last_refresh: Instant,
+    pub project_signatures: HashMap<String, u64>,
+    pub chats_signature: Option<u64>, // Of the current project's directory
+    pub focus: Focus,
+    pub details_scroll: usize,
+    pub details_height: usize, // Visible lines of the details pane, set while rendering
+    pub details_line_count: usize, // Wrapped lines of the details pane, set while rendering
+    details_uuid: Option<String>, // Message whose details are shown
+    pub details_match: Option<usize>, // Search match jumped to in the details pane
+    pub details_match_line: Option<usize>, // Content line to scroll to on the next render
+    pub viewer: Option<Viewer>, // Full-screen view of the selected message
+    pub raw_markdown: bool,    // Show assistant text as Markdown source
+    pub tool_calls: ToolCallIndex,
+    pub collapsed: HashSet<String>, // Messages whose descendants are hidden
+    pub active_path_only: bool,     // Hide the branches not followed
+    pub branch_choices: HashMap<String, String>, // Followed branch by the uuid of its parent
+    pub sidechain_filter: SidechainFilter,
+    pub hidden_categories: HashSet<MessageCategory>, // Sidechains go by `sidechain_filter`
+    content_lines_cache: Option<(String, Vec<Line<'static>>)>, // Highlighted content by message uuid
 }
 
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
 impl App {
     pub fn new(projects_dir: PathBuf, vertical_split: bool) -> Self {
+        // Tests keep out of the user's cache directory
+        let (index_path, totals_cache_path) = if cfg!(test) {
+            (None, None)
+        } else {
+            (
+                SearchIndex::default_path(&projects_dir),
+                ChatTotalsCache::default_path(&projects_dir),
+            )
+        };
         Self {
             screen: Screen::Projects,
             projects: ListManager::new(),
@@ -289,8 +407,50 @@ impl App {
             should_quit: false,
             search_mode: false,
             search_query: String::new(),
+            match_mode: MatchMode::default(),
+            search_error: None,
+            find_mode: false,
+            find_query: String::new(),
+            find_matches: Vec::new(),
             current_project: None,
             current_chat: None,
+            price_table: PriceTable::default(),
+            show_stats: false,
+            chat_stats: ChatStats::default(),
+            search_results: ListManager::new(),
+            content_search_mode: false,
+            content_search_query: String::new(),
+            content_search_scope: None,
+            opened_from_search: false,
+            index_path,
+            search_index: None,
+            totals_cache_path,
+            totals_cache: ChatTotalsCache::default(),
+            status_message: None,
+            follow_mode: false,
+            follow_pinned: false,
+            chat_file_path: None,
+            chat_file_offset: 0,
+            refresh_interval: Some(DEFAULT_REFRESH_INTERVAL),
+            last_refresh: Instant::now(),
+            project_signatures: HashMap::new(),
+            chats_signature: None,
+            focus: Focus::List,
+            details_scroll: 0,
+            details_height: 0,
+            details_line_count: 0,
+            details_uuid: None,
+            details_match: None,
+            details_match_line: None,
+            viewer: None,
+            raw_markdown: false,
+            tool_calls: ToolCallIndex::default(),
+            collapsed: HashSet::new(),
+            active_path_only: false,
+            branch_choices: HashMap::new(),
+            sidechain_filter: SidechainFilter::All,
+            hidden_categories: HashSet::new(),
+            content_lines_cache: None,
         }
     }
 
@@ -299,7 +459,20 @@ impl App {
     }
 
     pub fn load_projects(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.projects.items = crate::project::discover_projects(&self.projects_dir)?;
+        self.project_signatures.clear();
+        if let Some(path) = &self.totals_cache_path {
+            self.totals_cache = ChatTotalsCache::load(path);
+        }
+        self.projects.items =
+            crate::project::discover_projects(&self.projects_dir, &mut self.totals_cache)?;
+        self.save_totals_cache();
+        for project in &self.projects.items {
+            let project_dir = self.projects_dir.join(&project.name);
+            if let Ok(signature) = crate::project::project_dir_signature(&project_dir) {
+                self.project_signatures
+                    .insert(project.name.clone(), signature);
+            }
+        }
         self.projects.state = ListState::default();
         if !self.projects.is_empty() {
             self.projects.select(Some(0));
@@ -307,6 +480,138 @@ impl App {
         Ok(())
     }
 
+    fn save_totals_cache(&mut self) {
+        if let Some(path) = &self.totals_cache_path {
+            // Failing to persist the totals only means computing them again next time
+            let _ = self.totals_cache.save(path);
+        }
+    }
+
+    /// Rescan the projects directory and the open project's chats. Lists are
+    /// updated in place, keeping the selection and any active filter.
+    /// Returns whether anything changed.
+    pub fn refresh_lists(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.last_refresh = Instant::now();
+        let mut changed = self.refresh_projects()?;
+        if self.current_project.is_some() {
+            changed |= self.refresh_chats()?;
+        }
+        Ok(changed)
+    }
+
+    /// Manual refresh, reporting the outcome in the status bar.
+    pub fn refresh_now(&mut self) {
+        self.status_message = Some(match self.refresh_lists() {
+            Ok(true) => "Refreshed".to_string(),
+            Ok(false) => "Already up to date".to_string(),
+            Err(e) => format!("Refresh failed: {}", e),
+        });
+    }
+
+    fn refresh_projects(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut changed = false;
+        let mut projects = Vec::new();
+        let mut signatures = HashMap::new();
+
+        for project_dir in crate::project::project_dirs(&self.projects_dir)? {
+            // Projects removed while scanning are simply left out
+            let Ok(signature) = crate::project::project_dir_signature(&project_dir) else {
+                continue;
+            };
+            let name = project_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            // Only projects whose chat files changed are reloaded
+            let unchanged = self
+                .projects
+                .items
+                .iter()
+                .find(|p| p.name == name)
+                .filter(|_| self.project_signatures.get(&name) == Some(&signature));
+            let project = match unchanged {
+                Some(project) => project.clone(),
+                None => {
+                    // Only the chats that changed are parsed again
+                    let Ok(project) =
+                        crate::project::load_project(&project_dir, &mut self.totals_cache)
+                    else {
+                        continue;
+                    };
+                    changed = true;
+                    project
+                }
+            };
+            signatures.insert(name, signature);
+            projects.push(project);
+        }
+        // Every project kept from before is unchanged, so a shorter list means some were removed
+        changed |= projects.len() != self.projects.items.len();
+        if !changed {
+            return Ok(false);
+        }
+        self.save_totals_cache();
+
+        projects.sort_by_key(|p| std::cmp::Reverse(p.last_modified));
+        self.project_signatures = signatures;
+
+        if let Some(current) = self.current_project.as_mut() {
+            if let Some(project) = projects.iter().find(|p| p.name == current.name) {
+                *current = project.clone();
+            }
+        }
+
+        let selected_name = self.projects.selected_item().map(|p| p.name.clone());
+        let offset = self.projects.offset();
+        self.projects.items = projects;
+        self.reapply_search_filter(Screen::Projects);
+        self.projects
+            .select_first_where(|p| Some(&p.name) == selected_name.as_ref());
+        self.projects.restore_offset(offset);
+        Ok(true)
+    }
+
+    fn refresh_chats(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(project) = self.current_project.as_ref() else {
+            return Ok(false);
+        };
+        let project_dir = self.projects_dir.join(&project.name);
+        let signature = crate::project::project_dir_signature(&project_dir)?;
+        if self.chats_signature == Some(signature) {
+            return Ok(false);
+        }
+        // Only the chats that changed are counted again
+        let chats = crate::project::discover_chats(&project_dir, &mut self.totals_cache)?;
+        self.chats_signature = Some(signature);
+        self.save_totals_cache();
+
+        let selected_name = self.chats.selected_item().map(|c| c.name.clone());
+        let offset = self.chats.offset();
+        self.chats.items = chats;
+        self.reapply_search_filter(Screen::Chats);
+        self.chats
+            .select_first_where(|c| Some(&c.name) == selected_name.as_ref());
+        self.chats.restore_offset(offset);
+        Ok(true)
+    }
+
+    /// Filter a list again after its items were replaced. Only the list of
+    /// the current screen can have a filter.
+    fn reapply_search_filter(&mut self, screen: Screen) {
+        if self.screen == screen && !self.search_query.is_empty() {
+            self.apply_search_filter_with_preservation(false);
+            return;
+        }
+        match screen {
+            Screen::Projects => self.projects.clear_filter(),
+            Screen::Chats => self.chats.clear_filter(),
+            Screen::Messages => self.clear_message_filter(false),
+            Screen::SearchResults => self.search_results.clear_filter(),
+        }
+    }
+
     pub fn selected_project(&self) -> Option<&Project> {
         if self.screen == Screen::Projects {
             self.projects.selected_item()
@@ -315,6 +620,17 @@ impl App {
         }
     }
 
+    /// Message count and token totals across all projects.
+    pub fn global_totals(&self) -> (usize, TokenTotals) {
+        let mut message_count = 0;
+        let mut tokens = TokenTotals::default();
+        for project in &self.projects.items {
+            message_count += project.message_count;
+            tokens.add(&project.tokens);
+        }
+        (message_count, tokens)
+    }
+
     pub fn selected_chat(&self) -> Option<&Chat> {
         self.chats.selected_item()
     }
@@ -330,7 +646,10 @@ impl App {
             // Store the current project before clearing filters
             self.current_project = Some(project.clone());
 
-            self.chats.items = crate::project::discover_chats(&project_path)?;
+            self.chats.items =
+                crate::project::discover_chats(&project_path, &mut self.totals_cache)?;
+            self.save_totals_cache();
+            self.chats_signature = crate::project::project_dir_signature(&project_path).ok();
             self.chats.state = ListState::default();
             if !self.chats.is_empty() {
                 self.chats.select(Some(0));
@@ -345,30 +664,45 @@ impl App {
 
     pub fn open_chat(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if let (Some(project), Some(chat)) = (self.selected_project(), self.selected_chat()) {
-            let chat_path = self
-                .projects_dir
-                .join(&project.name)
-                .join(format!("{}.jsonl", chat.name));
+            let chat_path =
+                crate::project::chat_file_path(&self.projects_dir, &project.name, &chat.name);
 
             // Store the current chat before clearing filters
             self.current_chat = Some(chat.clone());
 
-            let messages = crate::project::load_messages(&chat_path)?;
+            let (messages, offset) = crate::project::load_messages_with_offset(&chat_path)?;
             self.messages.items = crate::project::build_message_hierarchy(messages);
+            self.tool_calls = ToolCallIndex::build(&self.messages.items);
+            self.collapsed.clear();
+            self.branch_choices.clear();
+            self.chat_file_path = Some(chat_path);
+            self.chat_file_offset = offset;
+            self.refresh_chat_stats();
             self.messages.state = ListState::default();
             if !self.messages.is_empty() {
                 self.messages.select(Some(0));
             }
             self.screen = Screen::Messages;
+            self.focus = Focus::List;
             self.search_mode = false;
             self.search_query.clear();
             self.clear_search_filter();
+            self.exit_find_mode();
+            // After clearing the filter, which selects the first message again
+            if self.follow_mode && self.follow_pinned {
+                self.messages.go_to_bottom();
+            }
         }
         Ok(())
     }
 
     pub fn go_back(&mut self) {
         match self.screen {
+            Screen::Messages if self.opened_from_search => {
+                self.screen = Screen::SearchResults;
+                self.messages.items.clear();
+                self.opened_from_search = false;
+            }
             Screen::Messages => {
                 self.screen = Screen::Chats;
                 self.messages.items.clear();
@@ -378,6 +712,10 @@ impl App {
                     self.find_and_select_chat(&current_chat.name);
                 }
             }
+            Screen::SearchResults => {
+                self.search_results.items.clear();
+                self.close_search_results();
+            }
             Screen::Chats => {
                 self.screen = Screen::Projects;
                 self.chats.items.clear();
@@ -394,9 +732,11 @@ impl App {
                 self.quit();
             }
         }
+        self.focus = Focus::List;
         self.search_mode = false;
         self.search_query.clear();
         self.clear_search_filter_with_preservation(true); // Preserve selection when clearing search
+        self.exit_find_mode();
     }
 
     fn current_list_mut(&mut self) -> &mut dyn ListManagerTrait {
@@ -404,6 +744,7 @@ impl App {
             Screen::Projects => &mut self.projects,
             Screen::Chats => &mut self.chats,
             Screen::Messages => &mut self.messages,
+            Screen::SearchResults => &mut self.search_results,
         }
     }
 
@@ -448,6 +789,7 @@ impl App {
             Screen::Projects => self.projects.scroll_selected_to_top(),
             Screen::Chats => self.chats.scroll_selected_to_top(),
             Screen::Messages => self.messages.scroll_selected_to_top(),
+            Screen::SearchResults => self.search_results.scroll_selected_to_top(),
         }
     }
 
@@ -456,6 +798,7 @@ impl App {
             Screen::Projects => self.projects.scroll_selected_to_center(page_size),
             Screen::Chats => self.chats.scroll_selected_to_center(page_size),
             Screen::Messages => self.messages.scroll_selected_to_center(page_size),
+            Screen::SearchResults => self.search_results.scroll_selected_to_center(page_size),
         }
     }
 
@@ -464,6 +807,7 @@ impl App {
             Screen::Projects => self.projects.scroll_selected_to_bottom(page_size),
             Screen::Chats => self.chats.scroll_selected_to_bottom(page_size),
             Screen::Messages => self.messages.scroll_selected_to_bottom(page_size),
+            Screen::SearchResults => self.search_results.scroll_selected_to_bottom(page_size),
         }
     }
 
@@ -477,7 +821,7 @@ impl App {
         // Find the next initial message after current selection
         for (i, hierarchical_message) in self
             .messages
-            .items
+            .active_items()
             .iter()
             .enumerate()
             .skip(current_selection + 1)
@@ -497,7 +841,7 @@ impl App {
         let current_selection = self.messages.selected().unwrap_or(0);
 
         // Find the previous initial message before current selection
-        for (i, hierarchical_message) in self.messages.items.iter().enumerate().rev() {
+        for (i, hierarchical_message) in self.messages.active_items().iter().enumerate().rev() {
             if i >= current_selection {
                 continue;
             }
@@ -512,7 +856,540 @@ impl App {
         self.vertical_split = !self.vertical_split;
     }
 
+    pub fn toggle_focus(&mut self) {
+        if self.screen != Screen::Messages {
+            return;
+        }
+        self.focus = match self.focus {
+            Focus::List => Focus::Details,
+            Focus::Details => Focus::List,
+        };
+    }
+
+    /// Collapse the subtree of the selected message, or expand it again.
+    pub fn toggle_collapse(&mut self) {
+        let Some(selected_message) = self.messages.selected_item() else {
+            return;
+        };
+        if selected_message.descendant_count == 0 {
+            self.status_message = Some("No messages follow from this one".to_string());
+            return;
+        }
+        let uuid = selected_message.message.uuid.clone();
+        if !self.collapsed.remove(&uuid) {
+            self.collapsed.insert(uuid);
+        }
+        self.update_message_view();
+    }
+
+    pub fn expand_all(&mut self) {
+        if self.collapsed.is_empty() {
+            return;
+        }
+        self.collapsed.clear();
+        self.update_message_view();
+    }
+
+    pub fn is_collapsed(&self, message: &HierarchicalMessage) -> bool {
+        self.collapsed.contains(&message.message.uuid)
+    }
+
+    /// Messages hidden inside collapsed subtrees, by the sidechain filter or
+    /// category toggles, or off the active path.
+    fn hidden_messages(&self) -> HashSet<String> {
+        let mut hidden = HashSet::new();
+        let mut hide_until = 0;
+        // Subtrees are listed right after their root, so each is a range of items
+        for (index, message) in self.messages.items.iter().enumerate() {
+            if index < hide_until
+                || !self.sidechain_filter.shows(message)
+                || (!self.hidden_categories.is_empty()
+                    && message.message.in_categories(&self.hidden_categories))
+            {
+                hidden.insert(message.message.uuid.clone());
+            }
+            if index >= hide_until && self.collapsed.contains(&message.message.uuid) {
+                hide_until = index + 1 + message.descendant_count;
+            }
+        }
+
+        if self.active_path_only {
+            // Parents come before their children, so a message is on the path
+            // when its parent is and it's the followed branch
+            let mut on_path: HashSet<&str> = HashSet::new();
+            let mut enclosing: Vec<(&str, usize)> = Vec::new(); // Subtree roots and their ends
+            for (index, message) in self.messages.items.iter().enumerate() {
+                let uuid = message.message.uuid.as_str();
+                while enclosing.last().is_some_and(|&(_, end)| end <= index) {
+                    enclosing.pop();
+                }
+                let tree_parent = enclosing.last().map(|&(uuid, _)| uuid);
+                enclosing.push((uuid, index + 1 + message.descendant_count));
+
+                let followed = match message.message.parent_uuid.as_deref() {
+                    // Sidechains without a parent are listed under the Task call
+                    // that spawned them, and are on the path when it is
+                    None => tree_parent.is_none_or(|task| on_path.contains(task)),
+                    Some(parent) => {
+                        on_path.contains(parent)
+                            && match (message.branch, self.branch_choices.get(parent)) {
+                                (None, _) => true,
+                                (Some(_), Some(choice)) => choice == uuid,
+                                // The newest branch is the one a session went on with
+                                (Some((position, count)), None) => position == count,
+                            }
+                    }
+                };
+                if followed {
+                    on_path.insert(uuid);
+                } else {
+                    hidden.insert(uuid.to_string());
+                }
+            }
+        }
+        hidden
+    }
+
+    /// Show all messages, hide sidechains, or show only sidechains.
+    pub fn cycle_sidechain_filter(&mut self) {
+        self.sidechain_filter = self.sidechain_filter.next();
+        self.update_message_view();
+    }
+
+    /// Hide the messages of a category, or show them again.
+    pub fn toggle_category(&mut self, category: MessageCategory) {
+        // Sidechains have a filter of their own, which can also show only them
+        let hidden = if category == MessageCategory::Sidechain {
+            self.sidechain_filter = match self.sidechain_filter {
+                SidechainFilter::Hide => SidechainFilter::All,
+                _ => SidechainFilter::Hide,
+            };
+            self.sidechain_filter == SidechainFilter::Hide
+        } else if self.hidden_categories.remove(&category) {
+            false
+        } else {
+            self.hidden_categories.insert(category);
+            true
+        };
+        self.update_message_view();
+
+        let label = category.label();
+        let mut label_chars = label.chars();
+        let capitalized: String = label_chars
+            .next()
+            .map(|first| first.to_uppercase().chain(label_chars).collect())
+            .unwrap_or_default();
+        self.status_message = Some(format!(
+            "{} {}",
+            capitalized,
+            if hidden { "hidden" } else { "shown" }
+        ));
+    }
+
+    /// Show only the followed branches, from each root to a leaf, or the
+    /// whole tree again.
+    pub fn toggle_active_path(&mut self) {
+        self.active_path_only = !self.active_path_only;
+        self.update_message_view();
+    }
+
+    /// The sibling branches of the branch the selected message is on: the
+    /// uuid of their parent, their uuids and the position of the selected one.
+    fn selected_branch(&self) -> Option<(String, Vec<String>, usize)> {
+        let items = &self.messages.items;
+        let index_by_uuid: HashMap<&str, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(index, message)| (message.message.uuid.as_str(), index))
+            .collect();
+
+        // Walk up to the closest message that starts a branch
+        let mut current = self.messages.selected_item()?;
+        while current.branch.is_none() {
+            let parent = current.message.parent_uuid.as_deref()?;
+            current = &items[*index_by_uuid.get(parent)?];
+        }
+
+        let parent = current.message.parent_uuid.clone()?;
+        let siblings: Vec<String> = items
+            .iter()
+            .filter(|message| {
+                message.branch.is_some() && message.message.parent_uuid.as_ref() == Some(&parent)
+            })
+            .map(|message| message.message.uuid.clone())
+            .collect();
+        let position = siblings
+            .iter()
+            .position(|uuid| *uuid == current.message.uuid)?;
+        Some((parent, siblings, position))
+    }
+
+    /// Switch to the next or previous sibling of the selected message's
+    /// branch, selecting its first message. With the active path shown,
+    /// the sibling becomes the followed branch.
+    pub fn switch_branch(&mut self, forward: bool) {
+        let Some((parent, siblings, position)) = self.selected_branch() else {
+            self.status_message = Some("No alternate branches here".to_string());
+            return;
+        };
+        let target = if forward {
+            (position + 1) % siblings.len()
+        } else {
+            (position + siblings.len() - 1) % siblings.len()
+        };
+        let target_uuid = siblings[target].clone();
+        self.branch_choices.insert(parent, target_uuid.clone());
+        self.update_message_view();
+
+        let target_index = self
+            .messages
+            .items
+            .iter()
+            .position(|message| message.message.uuid == target_uuid)
+            .and_then(|original_index| {
+                self.messages
+                    .find_original_index_in_filtered(original_index)
+            });
+        match target_index {
+            Some(index) => {
+                self.messages.select(Some(index));
+                self.status_message = Some(format!("Branch {}/{}", target + 1, siblings.len()));
+            }
+            None => self.status_message = Some("Branch is hidden by the filter".to_string()),
+        }
+    }
+
+    /// Filter the messages again after the hidden messages changed, keeping
+    /// the selection and scroll position.
+    fn update_message_view(&mut self) {
+        let offset = self.messages.offset();
+        if self.search_query.is_empty() {
+            self.clear_message_filter(true);
+        } else {
+            let query = self.search_query.clone();
+            self.apply_fuzzy_message_filter(&query);
+        }
+        self.messages.restore_offset(offset);
+        self.update_find_matches();
+    }
+
+    /// Remove the search filter from the messages, leaving out hidden ones.
+    fn clear_message_filter(&mut self, preserve_selection: bool) {
+        self.messages
+            .clear_filter_with_preservation(preserve_selection);
+        let hidden = self.hidden_messages();
+        if !hidden.is_empty() {
+            self.messages.apply_filter_with_selection_preservation(
+                |message| !hidden.contains(&message.message.uuid),
+                preserve_selection,
+            );
+        }
+    }
+
+    /// Highlighted content of the selected message. Highlighting is slow
+    /// enough that it's only redone when the selection changes.
+    pub fn selected_content_lines(&mut self) -> &[Line<'static>] {
+        let Some(selected_message) = self.messages.selected_item() else {
+            return &[];
+        };
+        let message = &selected_message.message;
+
+        let cached = self
+            .content_lines_cache
+            .as_ref()
+            .is_some_and(|(uuid, _)| *uuid == message.uuid);
+        if !cached {
+            let markdown = !self.raw_markdown && message.get_role() == "assistant";
+            let lines = crate::tools::render_message_content(
+                message,
+                &self.messages.items,
+                &self.tool_calls,
+                markdown,
+            );
+            self.content_lines_cache = Some((message.uuid.clone(), lines));
+        }
+
+        self.content_lines_cache
+            .as_ref()
+            .map(|(_, lines)| lines.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Select the message holding the result of the selected tool call, or
+    /// the call of the selected tool result.
+    pub fn go_to_paired_message(&mut self) {
+        let Some(selected_message) = self.messages.selected_item() else {
+            return;
+        };
+        let Some(original_index) = self.tool_calls.counterpart(&selected_message.message) else {
+            self.status_message = Some("No paired tool call or result".to_string());
+            return;
+        };
+        match self
+            .messages
+            .find_original_index_in_filtered(original_index)
+        {
+            Some(index) => self.messages.select(Some(index)),
+            None => {
+                self.status_message = Some("Paired message is hidden by the filter".to_string())
+            }
+        }
+    }
+
+    pub fn toggle_raw_markdown(&mut self) {
+        self.raw_markdown = !self.raw_markdown;
+        self.content_lines_cache = None;
+    }
+
+    /// Show the selected message's content full-screen.
+    pub fn open_viewer(&mut self) {
+        if self.screen != Screen::Messages {
+            return;
+        }
+        let Some(selected_index) = self.messages.selected() else {
+            return;
+        };
+        let Some(selected_message) = self.messages.selected_item() else {
+            return;
+        };
+        let message = &selected_message.message;
+        let title = format!(
+            "Message {} ({}, {})",
+            self.messages.original_index(selected_index) + 1,
+            message.get_role(),
+            message.timestamp.format("%Y-%m-%d %H:%M:%S")
+        );
+        self.viewer = Some(Viewer::new(title, &message.get_detailed_content()));
+    }
+
+    pub fn close_viewer(&mut self) {
+        self.viewer = None;
+    }
+
+    pub fn details_focused(&self) -> bool {
+        self.screen == Screen::Messages && self.focus == Focus::Details
+    }
+
+    /// Record the size of the rendered details pane. Scrolling starts over
+    /// whenever a different message is shown.
+    pub fn set_details_layout(&mut self, uuid: Option<&str>, line_count: usize, height: usize) {
+        if self.details_uuid.as_deref() != uuid {
+            self.details_uuid = uuid.map(str::to_string);
+            self.details_scroll = 0;
+            self.details_match = None;
+        }
+        self.details_line_count = line_count;
+        self.details_height = height;
+        self.details_scroll = self.details_scroll.min(self.max_details_scroll());
+    }
+
+    fn max_details_scroll(&self) -> usize {
+        self.details_line_count.saturating_sub(self.details_height)
+    }
+
+    pub fn scroll_details_up(&mut self, lines: usize) {
+        self.details_scroll = self.details_scroll.saturating_sub(lines);
+    }
+
+    pub fn scroll_details_down(&mut self, lines: usize) {
+        self.details_scroll = (self.details_scroll + lines).min(self.max_details_scroll());
+    }
+
+    pub fn scroll_details_to_top(&mut self) {
+        self.details_scroll = 0;
+    }
+
+    pub fn scroll_details_to_bottom(&mut self) {
+        self.details_scroll = self.max_details_scroll();
+    }
+
+    /// Periodic work done between key presses.
+    pub fn tick(&mut self) {
+        if self.follow_mode && self.screen == Screen::Messages {
+            if let Err(e) = self.poll_chat_file() {
+                self.status_message = Some(format!("Follow failed: {}", e));
+            }
+        }
+        if let Some(interval) = self.refresh_interval {
+            if self.last_refresh.elapsed() >= interval {
+                if let Err(e) = self.refresh_lists() {
+                    self.status_message = Some(format!("Refresh failed: {}", e));
+                }
+            }
+        }
+    }
+
+    pub fn toggle_follow_mode(&mut self) {
+        self.follow_mode = !self.follow_mode;
+        if self.follow_mode && self.follow_pinned {
+            self.messages.go_to_bottom();
+        }
+    }
+
+    pub fn toggle_follow_pinned(&mut self) {
+        self.follow_pinned = !self.follow_pinned;
+        if self.follow_mode && self.follow_pinned {
+            self.messages.go_to_bottom();
+        }
+    }
+
+    /// Load messages appended to the open chat file since it was last read.
+    /// Returns whether any new messages were found.
+    pub fn poll_chat_file(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(chat_path) = self.chat_file_path.clone() else {
+            return Ok(false);
+        };
+
+        let file_len = std::fs::metadata(&chat_path)?.len();
+        if file_len == self.chat_file_offset {
+            return Ok(false);
+        }
+
+        let new_messages = if file_len < self.chat_file_offset {
+            // The file was rewritten rather than appended to, start over
+            let (messages, offset) = crate::project::load_messages_with_offset(&chat_path)?;
+            self.messages.items.clear();
+            self.chat_file_offset = offset;
+            messages
+        } else {
+            let (messages, offset) =
+                crate::project::load_appended_messages(&chat_path, self.chat_file_offset)?;
+            self.chat_file_offset = offset;
+            messages
+        };
+        if new_messages.is_empty() {
+            return Ok(false);
+        }
+
+        let selected_uuid = self.selected_message().map(|m| m.message.uuid.clone());
+
+        // New messages may continue any earlier chain, so the hierarchy is rebuilt
+        let mut messages: Vec<_> = self
+            .messages
+            .items
+            .drain(..)
+            .map(|hierarchical| hierarchical.message)
+            .collect();
+        messages.extend(new_messages);
+        self.messages.items = crate::project::build_message_hierarchy(messages);
+        self.tool_calls = ToolCallIndex::build(&self.messages.items);
+        self.content_lines_cache = None; // A result may have arrived for the selected call
+        self.refresh_chat_stats();
+
+        self.reapply_search_filter(Screen::Messages);
+        self.update_find_matches();
+
+        if self.follow_pinned {
+            self.messages.go_to_bottom();
+        } else {
+            let selected_index = selected_uuid
+                .and_then(|uuid| {
+                    self.messages
+                        .items
+                        .iter()
+                        .position(|m| m.message.uuid == uuid)
+                })
+                .and_then(|original_idx| {
+                    self.messages.find_original_index_in_filtered(original_idx)
+                });
+            self.messages
+                .select(selected_index.or(Some(0).filter(|_| !self.messages.is_empty())));
+        }
+
+        Ok(true)
+    }
+
+    /// Write the open chat to a file in the current directory.
+    pub fn export_current_chat(&mut self, format: crate::export::ExportFormat) {
+        if self.screen != Screen::Messages {
+            return;
+        }
+        let (Some(project), Some(chat)) =
+            (self.current_project.as_ref(), self.current_chat.as_ref())
+        else {
+            return;
+        };
+
+        let default_path = crate::export::default_export_path(&chat.name, format);
+        let path = crate::export::unused_path(&default_path);
+        let result = crate::export::write_export(
+            &path,
+            format,
+            &project.name,
+            &chat.name,
+            &self.messages.items,
+        );
+        self.status_message = Some(match result {
+            Ok(()) if path != default_path => format!(
+                "Exported chat to {} ({} already exists)",
+                path.display(),
+                default_path.display()
+            ),
+            Ok(()) => format!("Exported chat to {}", path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    /// Write the images of the selected message to the current directory.
+    pub fn save_selected_images(&mut self) {
+        if self.screen != Screen::Messages {
+            return;
+        }
+        let Some(selected_message) = self.messages.selected_item() else {
+            return;
+        };
+        let message = &selected_message.message;
+        let images = message.images();
+        if images.is_empty() {
+            self.status_message = Some("No images in this message".to_string());
+            return;
+        }
+
+        let stem: String = message.uuid.chars().take(8).collect();
+        let result = crate::export::save_images(&images, std::path::Path::new("."), &stem);
+        self.status_message = Some(match result {
+            Ok((paths, _)) if paths.is_empty() => "Images have no data to save".to_string(),
+            Ok((paths, renamed)) => {
+                let mut status = format!(
+                    "Saved {}",
+                    paths
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                if renamed > 0 {
+                    status.push_str(" (existing files kept)");
+                }
+                status
+            }
+            Err(e) => format!("Saving images failed: {}", e),
+        });
+    }
+
+    pub fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+    }
+
+    pub fn refresh_chat_stats(&mut self) {
+        self.chat_stats = crate::stats::compute_chat_stats(
+            self.messages.items.iter().map(|m| &m.message),
+            &self.price_table,
+        );
+    }
+
+    /// Height of the usage stats pane on the messages screen, 0 when hidden.
+    pub fn stats_pane_height(&self) -> u16 {
+        if self.show_stats {
+            // Header, one row per model, total row and borders
+            (self.chat_stats.by_model.len() + 4) as u16
+        } else {
+            0
+        }
+    }
+
     pub fn enter_search_mode(&mut self) {
+        self.exit_find_mode();
         self.search_mode = true;
         self.search_query.clear();
         // Preserve selection when entering search mode
@@ -545,35 +1422,441 @@ impl App {
     }
 
     fn apply_search_filter_with_preservation(&mut self, preserve_selection: bool) {
+        self.search_error = None;
+        self.details_match = None;
         if self.search_query.is_empty() {
             self.clear_search_filter_with_preservation(preserve_selection);
             return;
         }
 
         let query = self.search_query.clone();
+        if self.screen == Screen::Messages {
+            self.apply_fuzzy_message_filter(&query);
+            return;
+        }
+        let Some(matcher) = self.matcher_for(&query) else {
+            return;
+        };
         match self.screen {
             Screen::Projects => {
                 self.projects
-                    .apply_fuzzy_filter_with_selection_preservation(
+                    .apply_matcher_filter_with_selection_preservation(
                         |project| &project.name,
-                        &query,
+                        &matcher,
                         preserve_selection,
                     );
             }
             Screen::Chats => {
-                self.chats.apply_fuzzy_filter_with_selection_preservation(
+                self.chats.apply_matcher_filter_with_selection_preservation(
                     |chat| &chat.name,
-                    &query,
+                    &matcher,
                     preserve_selection,
                 );
             }
-            Screen::Messages => {
-                self.apply_fuzzy_message_filter(&query);
+            Screen::Messages => {}
+            Screen::SearchResults => {
+                self.search_results
+                    .apply_matcher_filter_with_selection_preservation(
+                        |hit| &hit.snippet,
+                        &matcher,
+                        preserve_selection,
+                    );
+            }
+        }
+    }
+
+    /// Matcher for a query in the current match mode. An invalid regex is
+    /// reported in the status bar and leaves the list as it was, as it's
+    /// usually one that's still being typed.
+    fn matcher_for(&mut self, query: &str) -> Option<Matcher> {
+        match Matcher::new(self.match_mode, query) {
+            Ok(matcher) => Some(matcher),
+            Err(_) => {
+                self.search_error = Some("invalid pattern".to_string());
+                None
+            }
+        }
+    }
+
+    /// Switch to the next match mode, filtering again with it.
+    pub fn cycle_match_mode(&mut self) {
+        self.match_mode = self.match_mode.next();
+        self.apply_search_filter();
+        self.update_find_matches();
+    }
+
+    /// Matcher for highlighting the active search filter or find query, if
+    /// any. On the messages screen only the free text of the query is matched.
+    pub fn highlight_matcher(&self) -> Option<Matcher> {
+        let query = if self.screen == Screen::Messages {
+            let query = if self.find_query.is_empty() {
+                &self.search_query
+            } else {
+                &self.find_query
+            };
+            MessageQuery::parse(query, Utc::now()).text
+        } else {
+            self.search_query.clone()
+        };
+        if query.trim().is_empty() {
+            return None;
+        }
+        Matcher::new(self.match_mode, &query).ok()
+    }
+
+    /// Line index and byte range of each search match in the content of the
+    /// selected message, in order.
+    pub fn details_matches(&mut self) -> Vec<(usize, Range<usize>)> {
+        let Some(matcher) = self.highlight_matcher() else {
+            return Vec::new();
+        };
+        self.selected_content_lines()
+            .iter()
+            .enumerate()
+            .flat_map(|(line_index, line)| {
+                matcher
+                    .occurrences(&line.to_string())
+                    .into_iter()
+                    .map(move |range| (line_index, range))
+            })
+            .collect()
+    }
+
+    /// Jump to the next (or previous) search match in the details pane.
+    pub fn jump_to_details_match(&mut self, forward: bool) {
+        let matches = self.details_matches();
+        if matches.is_empty() {
+            self.details_match = None;
+            self.status_message = Some("No matches in this message".to_string());
+            return;
+        }
+
+        let count = matches.len();
+        let index = match (self.details_match, forward) {
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        self.details_match = Some(index);
+        self.details_match_line = Some(matches[index].0);
+        self.status_message = Some(format!("Match {}/{}", index + 1, count));
+    }
+
+    /// Status bar text while typing a search query.
+    pub fn search_status(&self) -> String {
+        let mut status = format!("/{}  [{}", self.search_query, self.match_mode.label());
+        if let Some(error) = &self.search_error {
+            status.push_str(&format!(": {}", error));
+        }
+        status.push(']');
+        status
+    }
+
+    /// Start typing a find query. Unlike a search, it leaves the messages in
+    /// their order and moves the selection between the ones found.
+    pub fn enter_find_mode(&mut self) {
+        if self.screen != Screen::Messages {
+            return;
+        }
+        if !self.search_query.is_empty() {
+            self.search_query.clear();
+            self.clear_message_filter(true);
+        }
+        self.find_mode = true;
+        self.find_query.clear();
+        self.find_matches.clear();
+    }
+
+    pub fn exit_find_mode(&mut self) {
+        self.find_mode = false;
+        self.find_query.clear();
+        self.find_matches.clear();
+    }
+
+    /// Stop typing the find query, keeping its matches for `n`/`N`.
+    pub fn confirm_find(&mut self) {
+        self.find_mode = false;
+    }
+
+    pub fn add_to_find_query(&mut self, c: char) {
+        self.find_query.push(c);
+        self.update_find_matches();
+        self.select_find_match_from_selection();
+    }
+
+    pub fn remove_from_find_query(&mut self) {
+        self.find_query.pop();
+        self.update_find_matches();
+        self.select_find_match_from_selection();
+    }
+
+    pub fn find_active(&self) -> bool {
+        !self.find_query.is_empty()
+    }
+
+    /// Look for the find query among the shown messages. An invalid regex
+    /// keeps the matches of the query before it.
+    fn update_find_matches(&mut self) {
+        self.search_error = None;
+        if self.find_query.is_empty() {
+            self.find_matches.clear();
+            return;
+        }
+
+        let query = MessageQuery::parse(&self.find_query, Utc::now());
+        let Some(matcher) = self.matcher_for(&query.text) else {
+            return;
+        };
+        let hidden = self.hidden_messages();
+        self.find_matches = self
+            .messages
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(original_index, message)| {
+                !hidden.contains(&message.message.uuid)
+                    && self
+                        .message_score(&query, &matcher, *original_index, message)
+                        .is_some()
+            })
+            .map(|(original_index, _)| original_index)
+            .collect();
+    }
+
+    /// Select the first match at or after the selected message, as the
+    /// query is typed.
+    fn select_find_match_from_selection(&mut self) {
+        let selected = self
+            .messages
+            .selected()
+            .map(|index| self.messages.original_index(index))
+            .unwrap_or(0);
+        let target = self
+            .find_matches
+            .iter()
+            .find(|&&index| index >= selected)
+            .or(self.find_matches.first())
+            .copied();
+        if let Some(target) = target {
+            self.select_original_message(target);
+        }
+    }
+
+    /// Select the next (or previous) message found by the find query,
+    /// wrapping around at the end.
+    pub fn jump_to_find_match(&mut self, forward: bool) {
+        if self.find_matches.is_empty() {
+            self.status_message = Some("No matches".to_string());
+            return;
+        }
+
+        let selected = self
+            .messages
+            .selected()
+            .map(|index| self.messages.original_index(index));
+        let target = match (selected, forward) {
+            (Some(selected), true) => self.find_matches.iter().find(|&&index| index > selected),
+            (Some(selected), false) => self
+                .find_matches
+                .iter()
+                .rev()
+                .find(|&&index| index < selected),
+            (None, _) => None,
+        };
+        let target = target.copied().unwrap_or(if forward {
+            self.find_matches[0]
+        } else {
+            self.find_matches[self.find_matches.len() - 1]
+        });
+        self.select_original_message(target);
+    }
+
+    fn select_original_message(&mut self, original_index: usize) {
+        if let Some(index) = self
+            .messages
+            .find_original_index_in_filtered(original_index)
+        {
+            self.messages.select(Some(index));
+        }
+    }
+
+    /// Status bar text while a find query is typed or active, with the
+    /// position of the selected message among the matches.
+    pub fn find_status(&self) -> String {
+        let mut status = format!("Find: {}  [{}", self.find_query, self.match_mode.label());
+        if let Some(error) = &self.search_error {
+            status.push_str(&format!(": {}", error));
+        }
+        status.push(']');
+
+        let selected = self
+            .messages
+            .selected()
+            .map(|index| self.messages.original_index(index));
+        let position =
+            selected.and_then(|selected| self.find_matches.binary_search(&selected).ok());
+        let found = match position {
+            _ if self.find_matches.is_empty() => "no matches".to_string(),
+            Some(position) => format!("match {}/{}", position + 1, self.find_matches.len()),
+            None => format!("{} matches", self.find_matches.len()),
+        };
+        if !self.find_query.is_empty() {
+            status.push_str(&format!("  {}", found));
+        }
+        status
+    }
+
+    /// Start typing a full-text query over all chats of the current project
+    /// (on the chats screen) or of all projects (on the projects screen).
+    pub fn enter_content_search_mode(&mut self) {
+        self.content_search_scope = match self.screen {
+            Screen::Projects => None,
+            Screen::Chats => self.current_project.as_ref().map(|p| p.name.clone()),
+            _ => return,
+        };
+        self.content_search_mode = true;
+        self.content_search_query.clear();
+    }
+
+    pub fn exit_content_search_mode(&mut self) {
+        self.content_search_mode = false;
+        self.content_search_query.clear();
+    }
+
+    pub fn add_to_content_search_query(&mut self, c: char) {
+        self.content_search_query.push(c);
+    }
+
+    pub fn remove_from_content_search_query(&mut self) {
+        self.content_search_query.pop();
+    }
+
+    pub fn run_content_search(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.content_search_mode = false;
+        if self.content_search_query.trim().is_empty() {
+            return Ok(());
+        }
+
+        let scope = self.content_search_scope.clone();
+        let query = self.content_search_query.clone();
+        self.search_results.items = self.find_messages(scope.as_deref(), &query)?;
+        self.search_results.state = ListState::default();
+        self.search_results.clear_filter();
+        self.screen = Screen::SearchResults;
+        self.search_mode = false;
+        self.search_query.clear();
+        self.clear_search_filter_with_preservation(true);
+        Ok(())
+    }
+
+    /// Full-text search through the on-disk index, refreshing it first, or by
+    /// scanning all chat files when the index is disabled.
+    pub fn find_messages(
+        &mut self,
+        project: Option<&str>,
+        query: &str,
+    ) -> Result<Vec<SearchHit>, Box<dyn std::error::Error>> {
+        let Some(index_path) = self.index_path.clone() else {
+            return crate::search::search_messages(&self.projects_dir, project, query);
+        };
+
+        let index = self
+            .search_index
+            .get_or_insert_with(|| SearchIndex::load(&index_path));
+        if index.update(&self.projects_dir)? {
+            // Failing to persist the index only means rebuilding it next time
+            if let Err(e) = index.save(&index_path) {
+                self.status_message = Some(format!("Saving the search index failed: {}", e));
+            }
+        }
+        Ok(index.search(project, query))
+    }
+
+    /// Open the chat of the selected search hit with the hit message selected.
+    pub fn open_search_hit(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(hit) = self.search_results.selected_item().cloned() else {
+            return Ok(());
+        };
+
+        self.projects.clear_filter();
+        self.find_and_select_project(&hit.project);
+        self.screen = Screen::Projects;
+        self.open_project()?;
+        self.find_and_select_chat(&hit.chat);
+        self.open_chat()?;
+
+        if hit.message_index < self.messages.items.len() {
+            match self
+                .messages
+                .find_original_index_in_filtered(hit.message_index)
+            {
+                Some(index) => self.messages.select(Some(index)),
+                None => {
+                    self.status_message =
+                        Some("Matching message is hidden by the filter".to_string())
+                }
+            }
+        }
+        self.opened_from_search = true;
+        Ok(())
+    }
+
+    fn close_search_results(&mut self) {
+        self.opened_from_search = false;
+        match self.content_search_scope.clone() {
+            Some(project_name) => {
+                // Opening hits may have switched projects, go back to the one searched
+                self.projects.clear_filter();
+                self.find_and_select_project(&project_name);
+                self.screen = Screen::Projects;
+                if let Err(e) = self.open_project() {
+                    self.status_message = Some(format!("Open failed: {}", e));
+                }
+            }
+            None => {
+                self.screen = Screen::Projects;
+                self.current_project = None;
+                self.current_chat = None;
             }
         }
     }
 
+    /// How well a message matches a query, or `None` when it doesn't. The
+    /// free text is matched against both the message number and content,
+    /// taking the best score; queries of only qualifiers score 0. Outside
+    /// fuzzy mode the number only matches when it's the whole query.
+    fn message_score(
+        &self,
+        query: &MessageQuery,
+        matcher: &Matcher,
+        original_index: usize,
+        message: &HierarchicalMessage,
+    ) -> Option<isize> {
+        if !query.matches(&message.message, &self.messages.items, &self.tool_calls) {
+            return None;
+        }
+        if query.text.is_empty() {
+            return Some(0);
+        }
+        let message_number = (original_index + 1).to_string();
+        let number_score = match matcher {
+            Matcher::Fuzzy(_) => matcher.score(&message_number),
+            // Substrings and patterns like `\d` would match almost every number
+            _ => (query.text.trim() == message_number).then_some(0),
+        };
+        let content_text = message.message.get_content_text();
+        number_score.max(matcher.score(&content_text))
+    }
+
+    /// Filter messages by a query's qualifiers, then match its free text in
+    /// the current match mode. Fuzzy matches are ranked by how well they match.
     pub fn apply_fuzzy_message_filter(&mut self, query: &str) {
+        let query = MessageQuery::parse(query, Utc::now());
+        let Some(matcher) = self.matcher_for(&query.text) else {
+            return;
+        };
+
         // Remember the currently selected original index
         let current_original_index = self
             .messages
@@ -582,34 +1865,24 @@ impl App {
 
         // Create a vector of (message, original_index, score) for items that match
         let mut matches: Vec<(HierarchicalMessage, usize, isize)> = Vec::new();
+        let hidden = self.hidden_messages();
 
         for (original_index, message) in self.messages.items.iter().enumerate() {
-            let message_number = (original_index + 1).to_string();
-            let content_text = message.message.get_content_text();
-
-            // Try fuzzy matching against both message number and content
-            let number_match = best_match(query, &message_number);
-            let content_match = best_match(query, &content_text);
-
-            // Take the best score from either match
-            let best_score = match (number_match, content_match) {
-                (Some(n), Some(c)) => Some(n.score().max(c.score())),
-                (Some(n), None) => Some(n.score()),
-                (None, Some(c)) => Some(c.score()),
-                (None, None) => None,
-            };
-
-            if let Some(score) = best_score {
+            if hidden.contains(&message.message.uuid) {
+                continue;
+            }
+            if let Some(score) = self.message_score(&query, &matcher, original_index, message) {
                 matches.push((message.clone(), original_index, score));
             }
         }
 
         // Sort by score (descending - higher scores are better)
-        matches.sort_by(|a, b| b.2.cmp(&a.2));
+        matches.sort_by_key(|m| std::cmp::Reverse(m.2));
 
         // Extract the sorted items and indices
         self.messages.filtered_items = matches.iter().map(|(item, _, _)| item.clone()).collect();
         self.messages.filtered_indices = matches.iter().map(|(_, idx, _)| *idx).collect();
+        self.messages.filter_active = true;
 
         // Try to preserve selection
         let new_selection = if !self.messages.filtered_items.is_empty() {
@@ -653,8 +1926,13 @@ impl App {
 
         self.messages.filtered_items.clear();
         self.messages.filtered_indices.clear();
+        self.messages.filter_active = true;
+        let hidden = self.hidden_messages();
 
         for (original_index, message) in self.messages.items.iter().enumerate() {
+            if hidden.contains(&message.message.uuid) {
+                continue;
+            }
             let message_number = (original_index + 1).to_string();
             let content_text = message.message.get_content_text().to_lowercase();
 
@@ -707,7 +1985,8 @@ impl App {
             .clear_filter_with_preservation(preserve_selection);
         self.chats
             .clear_filter_with_preservation(preserve_selection);
-        self.messages
+        self.clear_message_filter(preserve_selection);
+        self.search_results
             .clear_filter_with_preservation(preserve_selection);
     }
 
@@ -751,6 +2030,21 @@ impl<T: Clone> ListManager<T> {
         preserve_selection: bool,
     ) where
         F: Fn(&T) -> &str,
+    {
+        self.apply_matcher_filter_with_selection_preservation(
+            text_extractor,
+            &Matcher::Fuzzy(query.to_string()),
+            preserve_selection,
+        );
+    }
+
+    pub fn apply_matcher_filter_with_selection_preservation<F>(
+        &mut self,
+        text_extractor: F,
+        matcher: &Matcher,
+        preserve_selection: bool,
+    ) where
+        F: Fn(&T) -> &str,
     {
         // Remember the currently selected original index
         let current_original_index = if preserve_selection {
@@ -764,18 +2058,18 @@ impl<T: Clone> ListManager<T> {
         let mut matches: Vec<(T, usize, isize)> = Vec::new();
 
         for (original_index, item) in self.items.iter().enumerate() {
-            let text = text_extractor(item);
-            if let Some(result) = best_match(query, text) {
-                matches.push((item.clone(), original_index, result.score()));
+            if let Some(score) = matcher.score(text_extractor(item)) {
+                matches.push((item.clone(), original_index, score));
             }
         }
 
         // Sort by score (descending - higher scores are better)
-        matches.sort_by(|a, b| b.2.cmp(&a.2));
+        matches.sort_by_key(|m| std::cmp::Reverse(m.2));
 
         // Extract the sorted items and indices
         self.filtered_items = matches.iter().map(|(item, _, _)| item.clone()).collect();
         self.filtered_indices = matches.iter().map(|(_, idx, _)| *idx).collect();
+        self.filter_active = true;
 
         // Try to preserve selection
         let new_selection = if preserve_selection && !self.filtered_items.is_empty() {
@@ -827,6 +2121,7 @@ impl<T: Clone> ListManager<T> {
 
         self.filtered_items.clear();
         self.filtered_indices.clear();
+        self.filter_active = true;
 
         for (original_index, item) in self.items.iter().enumerate() {
             if predicate(item) {
@@ -888,6 +2183,7 @@ impl<T: Clone> ListManager<T> {
 
         self.filtered_items.clear();
         self.filtered_indices.clear();
+        self.filter_active = false;
 
         // Restore selection
         let new_selection = if preserve_selection {
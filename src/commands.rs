@@ -0,0 +1,149 @@
+use crate::export::format_timestamp;
+use crate::index::SearchIndex;
+use crate::project::{
+    build_message_hierarchy, chat_file_path, discover_chats, discover_projects, load_messages,
+    ChatTotalsCache, Message,
+};
+use crate::search::{search_messages, SearchHit};
+use std::path::Path;
+
+// Output of the non-interactive subcommands. Plain text is one tab-separated
+// line per item so it's easy to process with `cut`, `awk` and friends.
+
+/// Projects with their totals. Uses the on-disk totals cache when
+/// `totals_cache_path` is given.
+pub fn list_projects(
+    projects_dir: &Path,
+    totals_cache_path: Option<&Path>,
+    json: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut totals_cache = totals_cache_path
+        .map(ChatTotalsCache::load)
+        .unwrap_or_default();
+    let projects = discover_projects(projects_dir, &mut totals_cache)?;
+    if let Some(cache_path) = totals_cache_path {
+        // Failing to persist the totals only means computing them again next time
+        let _ = totals_cache.save(cache_path);
+    }
+    if json {
+        return Ok(serde_json::to_string_pretty(&projects)?);
+    }
+
+    let mut out = String::new();
+    for project in projects {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            project.name,
+            project.last_modified.to_rfc3339(),
+            project.chat_count,
+            project.message_count,
+            project.tokens.total()
+        ));
+    }
+    Ok(out)
+}
+
+/// Chats of a project. Uses the on-disk totals cache when
+/// `totals_cache_path` is given.
+pub fn list_chats(
+    projects_dir: &Path,
+    project_name: &str,
+    totals_cache_path: Option<&Path>,
+    json: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut totals_cache = totals_cache_path
+        .map(ChatTotalsCache::load)
+        .unwrap_or_default();
+    let chats = discover_chats(&projects_dir.join(project_name), &mut totals_cache)?;
+    if let Some(cache_path) = totals_cache_path {
+        // Failing to persist the totals only means computing them again next time
+        let _ = totals_cache.save(cache_path);
+    }
+    if json {
+        return Ok(serde_json::to_string_pretty(&chats)?);
+    }
+
+    let mut out = String::new();
+    for chat in chats {
+        out.push_str(&format!(
+            "{}\t{}\t{}\n",
+            chat.name,
+            chat.last_modified.to_rfc3339(),
+            chat.message_count
+        ));
+    }
+    Ok(out)
+}
+
+/// Messages of a chat in the same order and numbering as the messages view.
+pub fn show_chat(
+    projects_dir: &Path,
+    project_name: &str,
+    chat_name: &str,
+    json: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let messages = load_messages(&chat_file_path(projects_dir, project_name, chat_name))?;
+    let messages: Vec<Message> = build_message_hierarchy(messages)
+        .into_iter()
+        .map(|hierarchical| hierarchical.message)
+        .collect();
+    if json {
+        return Ok(serde_json::to_string_pretty(&messages)?);
+    }
+
+    let mut out = String::new();
+    for (index, message) in messages.iter().enumerate() {
+        out.push_str(&format!(
+            "#{}\t{}\t{}",
+            index + 1,
+            message.get_role(),
+            format_timestamp(&message.timestamp)
+        ));
+        if let Some(model) = message.get_model() {
+            out.push_str(&format!("\t{}", model));
+        }
+        out.push('\n');
+        out.push_str(message.get_detailed_content().trim_end());
+        out.push_str("\n\n");
+    }
+    Ok(out)
+}
+
+/// Full-text search over all chats, or the chats of one project. Uses the
+/// on-disk index when `index_path` is given.
+pub fn search(
+    projects_dir: &Path,
+    project_name: Option<&str>,
+    query: &str,
+    index_path: Option<&Path>,
+    json: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let hits: Vec<SearchHit> = match index_path {
+        Some(index_path) => {
+            let mut index = SearchIndex::load(index_path);
+            if index.update(projects_dir)? {
+                // Failing to persist the index only means rebuilding it next time
+                let _ = index.save(index_path);
+            }
+            index.search(project_name, query)
+        }
+        None => search_messages(projects_dir, project_name, query)?,
+    };
+    if json {
+        return Ok(serde_json::to_string_pretty(&hits)?);
+    }
+
+    let mut out = String::new();
+    for hit in hits {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            hit.project,
+            hit.chat,
+            hit.message_index + 1,
+            hit.role,
+            hit.timestamp.to_rfc3339(),
+            hit.snippet
+        ));
+    }
+    Ok(out)
+}
@@ -1,4 +1,5 @@
 use crate::app::App;
+use crate::export::ExportFormat;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::layout::Rect;
 use std::io;
@@ -15,11 +16,16 @@ pub fn handle_events(app: &mut App, terminal_area: Rect) -> io::Result<()> {
 }
 
 fn handle_key_event(app: &mut App, key: KeyEvent, terminal_area: Rect) {
+    app.status_message = None;
+
     // Calculate page size based on the current screen
     let page_size = match app.screen {
         crate::app::Screen::Messages => {
             // In messages view, we have split panes, so calculate based on the actual list area
-            let main_area_height = terminal_area.height.saturating_sub(1); // Subtract status bar
+            let main_area_height = terminal_area
+                .height
+                .saturating_sub(1) // Subtract status bar
+                .saturating_sub(app.stats_pane_height());
             let list_area_height = if app.vertical_split {
                 // Vertical split (Direction::Vertical): message list on top, half height
                 (main_area_height / 2).saturating_sub(2) // Half height minus borders
@@ -35,8 +41,16 @@ fn handle_key_event(app: &mut App, key: KeyEvent, terminal_area: Rect) {
         }
     };
 
-    if app.search_mode {
+    if app.viewer.is_some() {
+        handle_viewer_key(app, key);
+    } else if app.content_search_mode {
+        handle_content_search_mode_key(app, key);
+    } else if app.search_mode {
         handle_search_mode_key(app, key);
+    } else if app.find_mode {
+        handle_find_mode_key(app, key);
+    } else if app.details_focused() {
+        handle_details_focus_key(app, key, page_size);
     } else {
         handle_normal_mode_key(app, key, page_size);
     }
@@ -47,17 +61,113 @@ fn handle_search_mode_key(app: &mut App, key: KeyEvent) {
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.quit(),
         KeyCode::Esc => app.exit_search_mode(),
         KeyCode::Enter => app.exit_search_mode_keep_filter(),
+        KeyCode::Tab => app.cycle_match_mode(),
         KeyCode::Backspace => app.remove_from_search_query(),
         KeyCode::Char(c) => app.add_to_search_query(c),
         _ => {}
     }
 }
 
+fn handle_find_mode_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.quit(),
+        KeyCode::Esc => app.exit_find_mode(),
+        KeyCode::Enter => app.confirm_find(),
+        KeyCode::Tab => app.cycle_match_mode(),
+        KeyCode::Backspace => app.remove_from_find_query(),
+        KeyCode::Char(c) => app.add_to_find_query(c),
+        _ => {}
+    }
+}
+
+fn handle_content_search_mode_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.quit(),
+        KeyCode::Esc => app.exit_content_search_mode(),
+        KeyCode::Enter => {
+            if let Err(e) = app.run_content_search() {
+                app.status_message = Some(format!("Search failed: {}", e));
+            }
+        }
+        KeyCode::Backspace => app.remove_from_content_search_query(),
+        KeyCode::Char(c) => app.add_to_content_search_query(c),
+        _ => {}
+    }
+}
+
+fn handle_viewer_key(app: &mut App, key: KeyEvent) {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.quit();
+        return;
+    }
+    let Some(viewer) = app.viewer.as_mut() else {
+        return;
+    };
+
+    if viewer.search_mode {
+        match key.code {
+            KeyCode::Esc => viewer.cancel_search(),
+            KeyCode::Enter => viewer.confirm_search(),
+            KeyCode::Backspace => viewer.remove_from_search_query(),
+            KeyCode::Char(c) => viewer.add_to_search_query(c),
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('q') => app.quit(),
+        KeyCode::Esc | KeyCode::Char('h') | KeyCode::Char('v') => app.close_viewer(),
+        KeyCode::Up | KeyCode::Char('k') => viewer.scroll_up(1),
+        KeyCode::Down | KeyCode::Char('j') => viewer.scroll_down(1),
+        KeyCode::PageUp => viewer.page_up(),
+        KeyCode::PageDown => viewer.page_down(),
+        KeyCode::Char(' ') => {
+            if key.modifiers.contains(KeyModifiers::SHIFT) {
+                viewer.page_up();
+            } else {
+                viewer.page_down();
+            }
+        }
+        KeyCode::Char('g') => viewer.go_to_top(),
+        KeyCode::Char('G') => viewer.go_to_bottom(),
+        KeyCode::Char('/') => viewer.enter_search_mode(),
+        KeyCode::Char('n') => viewer.next_match(),
+        KeyCode::Char('N') => viewer.previous_match(),
+        _ => {}
+    }
+}
+
+// Scrolling keys move the details pane while it has focus, everything else
+// works as usual
+fn handle_details_focus_key(app: &mut App, key: KeyEvent, page_size: usize) {
+    let details_page = app.details_height.max(1);
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('h') => app.toggle_focus(),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_details_up(1),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_details_down(1),
+        KeyCode::PageUp => app.scroll_details_up(details_page),
+        KeyCode::PageDown => app.scroll_details_down(details_page),
+        KeyCode::Char(' ') => {
+            if key.modifiers.contains(KeyModifiers::SHIFT) {
+                app.scroll_details_up(details_page);
+            } else {
+                app.scroll_details_down(details_page);
+            }
+        }
+        KeyCode::Char('g') => app.scroll_details_to_top(),
+        KeyCode::Char('G') => app.scroll_details_to_bottom(),
+        _ => handle_normal_mode_key(app, key, page_size),
+    }
+}
+
 fn handle_normal_mode_key(app: &mut App, key: KeyEvent, page_size: usize) {
     match key.code {
+        KeyCode::Tab => app.toggle_focus(),
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.quit(),
         KeyCode::Char('q') => app.quit(),
         KeyCode::Char('/') => app.enter_search_mode(),
+        KeyCode::Char('F') => app.enter_content_search_mode(),
         KeyCode::Esc | KeyCode::Char('h') => app.go_back(),
         KeyCode::Up | KeyCode::Char('k') => app.move_selection_up_with_size(page_size),
         KeyCode::Down | KeyCode::Char('j') => app.move_selection_down_with_size(page_size),
@@ -74,10 +184,14 @@ fn handle_normal_mode_key(app: &mut App, key: KeyEvent, page_size: usize) {
             let result = match app.screen {
                 crate::app::Screen::Projects => app.open_project(),
                 crate::app::Screen::Chats => app.open_chat(),
-                crate::app::Screen::Messages => Ok(()), // No further navigation from messages
+                crate::app::Screen::Messages => {
+                    app.open_viewer();
+                    Ok(())
+                }
+                crate::app::Screen::SearchResults => app.open_search_hit(),
             };
             if let Err(e) = result {
-                eprintln!("Error opening: {}", e);
+                app.status_message = Some(format!("Open failed: {}", e));
             }
         }
         KeyCode::Char('g') => app.go_to_top(),
@@ -92,6 +206,76 @@ fn handle_normal_mode_key(app: &mut App, key: KeyEvent, page_size: usize) {
                 app.toggle_split();
             }
         }
+        KeyCode::Char('r') => app.refresh_now(),
+        KeyCode::Char('v') => app.open_viewer(),
+        KeyCode::Char('e') => app.export_current_chat(ExportFormat::Markdown),
+        KeyCode::Char('E') => app.export_current_chat(ExportFormat::Html),
+        KeyCode::Char('T') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.toggle_follow_mode();
+            }
+        }
+        KeyCode::Char('P') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.toggle_follow_pinned();
+            }
+        }
+        KeyCode::Char('c') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.toggle_collapse();
+            }
+        }
+        KeyCode::Char('C') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.expand_all();
+            }
+        }
+        KeyCode::Char('[') | KeyCode::Char(']') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.switch_branch(key.code == KeyCode::Char(']'));
+            }
+        }
+        KeyCode::Char('B') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.toggle_active_path();
+            }
+        }
+        KeyCode::Char('S') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.cycle_sidechain_filter();
+            }
+        }
+        KeyCode::Char(digit @ '1'..='7') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                let index = digit as usize - '1' as usize;
+                app.toggle_category(crate::project::MessageCategory::ALL[index]);
+            }
+        }
+        KeyCode::Char('f') => app.enter_find_mode(),
+        KeyCode::Char('n') | KeyCode::Char('N') => {
+            let forward = key.code == KeyCode::Char('n');
+            if app.find_active() {
+                app.jump_to_find_match(forward);
+            } else if matches!(app.screen, crate::app::Screen::Messages) {
+                app.jump_to_details_match(forward);
+            }
+        }
+        KeyCode::Char('i') => app.save_selected_images(),
+        KeyCode::Char('p') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.go_to_paired_message();
+            }
+        }
+        KeyCode::Char('m') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.toggle_raw_markdown();
+            }
+        }
+        KeyCode::Char('u') => {
+            if matches!(app.screen, crate::app::Screen::Messages) {
+                app.toggle_stats();
+            }
+        }
         _ => {}
     }
 }
@@ -0,0 +1,492 @@
+use crate::project::{
+    build_message_hierarchy, chat_file_path, load_messages, ContentBlock, HierarchicalMessage,
+    ImageBlock,
+};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Session metadata collected from the messages of a chat.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionInfo {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    pub version: Option<String>,
+    pub models: Vec<String>,
+    pub started: Option<DateTime<Utc>>,
+    pub ended: Option<DateTime<Utc>>,
+}
+
+pub fn session_info(messages: &[HierarchicalMessage]) -> SessionInfo {
+    let mut info = SessionInfo::default();
+
+    for message in messages.iter().map(|m| &m.message) {
+        if info.session_id.is_none() {
+            info.session_id = message.session_id.clone();
+        }
+        if info.cwd.is_none() {
+            info.cwd = message.cwd.clone();
+        }
+        if info.git_branch.is_none() {
+            info.git_branch = message.git_branch.clone();
+        }
+        if info.version.is_none() {
+            info.version = message.version.clone();
+        }
+        if let Some(model) = message.get_model() {
+            if !info.models.iter().any(|m| m == model) {
+                info.models.push(model.to_string());
+            }
+        }
+
+        // Messages without a timestamp get the epoch, which isn't real activity
+        if message.timestamp.timestamp() != 0 {
+            if info
+                .started
+                .is_none_or(|started| message.timestamp < started)
+            {
+                info.started = Some(message.timestamp);
+            }
+            if info.ended.is_none_or(|ended| message.timestamp > ended) {
+                info.ended = Some(message.timestamp);
+            }
+        }
+    }
+
+    info
+}
+
+pub fn role_title(role: &str) -> String {
+    let mut chars = role.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Unknown".to_string(),
+    }
+}
+
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// A code fence longer than any run of backticks in `text`, so the text can't
+/// close it early.
+fn code_fence(text: &str) -> String {
+    let mut longest_run = 0;
+    let mut current_run = 0;
+    for c in text.chars() {
+        if c == '`' {
+            current_run += 1;
+            longest_run = longest_run.max(current_run);
+        } else {
+            current_run = 0;
+        }
+    }
+    "`".repeat((longest_run + 1).max(3))
+}
+
+fn push_code_block(out: &mut String, text: &str, language: &str) {
+    let fence = code_fence(text);
+    out.push_str(&format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        language,
+        text.trim_end(),
+        fence
+    ));
+}
+
+pub fn chat_to_markdown(
+    project_name: &str,
+    chat_name: &str,
+    messages: &[HierarchicalMessage],
+) -> String {
+    let info = session_info(messages);
+    let mut out = format!("# {}\n\n", chat_name);
+
+    out.push_str(&format!("- **Project:** {}\n", project_name));
+    if let Some(session_id) = &info.session_id {
+        out.push_str(&format!("- **Session:** {}\n", session_id));
+    }
+    if let Some(cwd) = &info.cwd {
+        out.push_str(&format!("- **Working Dir:** `{}`\n", cwd));
+    }
+    if let Some(git_branch) = &info.git_branch {
+        out.push_str(&format!("- **Git Branch:** `{}`\n", git_branch));
+    }
+    if let Some(version) = &info.version {
+        out.push_str(&format!("- **Version:** {}\n", version));
+    }
+    if !info.models.is_empty() {
+        out.push_str(&format!("- **Models:** {}\n", info.models.join(", ")));
+    }
+    if let (Some(started), Some(ended)) = (info.started, info.ended) {
+        out.push_str(&format!(
+            "- **Time:** {} to {}\n",
+            format_timestamp(&started),
+            format_timestamp(&ended)
+        ));
+    }
+    out.push_str(&format!("- **Messages:** {}\n\n", messages.len()));
+
+    for (index, hierarchical) in messages.iter().enumerate() {
+        let message = &hierarchical.message;
+
+        let mut heading = format!("## {}. {}", index + 1, role_title(message.get_role()));
+        if let Some(model) = message.get_model() {
+            heading.push_str(&format!(" ({})", model));
+        }
+        heading.push_str(&format!(" - {}\n\n", format_timestamp(&message.timestamp)));
+        out.push_str(&heading);
+
+        let blocks = message.content_blocks();
+        if blocks.is_empty() {
+            out.push_str(&format!("*[{}]*\n\n", message.msg_type));
+        }
+
+        for block in blocks {
+            match block {
+                ContentBlock::Text(text) => {
+                    out.push_str(text.trim_end());
+                    out.push_str("\n\n");
+                }
+                ContentBlock::Thinking(thinking) => {
+                    out.push_str("<details>\n<summary>Thinking</summary>\n\n");
+                    out.push_str(thinking.trim_end());
+                    out.push_str("\n\n</details>\n\n");
+                }
+                ContentBlock::ToolUse { name, input, .. } => {
+                    out.push_str(&format!("**Tool: {}**\n\n", name));
+                    let input_str =
+                        serde_json::to_string_pretty(&input).unwrap_or_else(|_| input.to_string());
+                    push_code_block(&mut out, &input_str, "json");
+                }
+                ContentBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    out.push_str(if is_error {
+                        "**Tool Result (error)**\n\n"
+                    } else {
+                        "**Tool Result**\n\n"
+                    });
+                    push_code_block(&mut out, &content, "");
+                }
+                ContentBlock::Image(image) => {
+                    out.push_str(&format!("*{}*\n\n", image.placeholder()));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 0; background: #f6f7f9; color: #1f2328; }
+header, main { max-width: 960px; margin: 0 auto; padding: 0 16px; }
+header { padding-top: 24px; }
+header dl { display: grid; grid-template-columns: max-content 1fr; gap: 4px 16px; font-size: 14px; }
+header dt { font-weight: 600; color: #59636e; }
+header dd { margin: 0; }
+.message { background: #fff; border: 1px solid #d1d9e0; border-radius: 6px; margin: 16px 0; padding: 12px 16px; }
+.message.user { border-left: 4px solid #0969da; }
+.message.assistant { border-left: 4px solid #8250df; }
+.message.initial { margin-top: 32px; }
+.meta { font-size: 13px; color: #59636e; margin-bottom: 8px; }
+.meta a { color: inherit; text-decoration: none; font-weight: 600; }
+.meta .role { font-weight: 600; color: #1f2328; margin: 0 6px; }
+.text { white-space: pre-wrap; overflow-wrap: anywhere; line-height: 1.5; }
+details { margin: 8px 0; border: 1px solid #d1d9e0; border-radius: 6px; background: #f6f8fa; }
+details > summary { cursor: pointer; padding: 6px 10px; font-size: 13px; font-weight: 600; }
+details > .text, details > pre { margin: 0; padding: 8px 10px; border-top: 1px solid #d1d9e0; }
+details.thinking { font-style: italic; }
+details.error > summary { color: #cf222e; }
+pre { overflow-x: auto; font-size: 13px; }
+img.image { display: block; max-width: 100%; margin: 8px 0; border: 1px solid #d1d9e0; border-radius: 6px; }
+.empty { color: #59636e; font-style: italic; }
+"#;
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Anchor id of a message, so links to single messages can be shared.
+pub fn message_anchor(uuid: &str) -> String {
+    let safe_uuid: String = uuid
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("msg-{}", safe_uuid)
+}
+
+pub fn chat_to_html(
+    project_name: &str,
+    chat_name: &str,
+    messages: &[HierarchicalMessage],
+) -> String {
+    let info = session_info(messages);
+    let mut out = String::new();
+
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!(
+        "<title>{} - {}</title>\n",
+        escape_html(chat_name),
+        escape_html(project_name)
+    ));
+    out.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", HTML_STYLE));
+
+    // Session metadata
+    out.push_str(&format!(
+        "<header>\n<h1>{}</h1>\n<dl>\n",
+        escape_html(chat_name)
+    ));
+    let mut push_field = |name: &str, value: &str| {
+        out.push_str(&format!(
+            "<dt>{}</dt><dd>{}</dd>\n",
+            name,
+            escape_html(value)
+        ));
+    };
+    push_field("Project", project_name);
+    if let Some(session_id) = &info.session_id {
+        push_field("Session", session_id);
+    }
+    if let Some(cwd) = &info.cwd {
+        push_field("Working Dir", cwd);
+    }
+    if let Some(git_branch) = &info.git_branch {
+        push_field("Git Branch", git_branch);
+    }
+    if let Some(version) = &info.version {
+        push_field("Version", version);
+    }
+    if !info.models.is_empty() {
+        push_field("Models", &info.models.join(", "));
+    }
+    if let (Some(started), Some(ended)) = (info.started, info.ended) {
+        push_field(
+            "Time",
+            &format!(
+                "{} to {}",
+                format_timestamp(&started),
+                format_timestamp(&ended)
+            ),
+        );
+    }
+    push_field("Messages", &messages.len().to_string());
+    out.push_str("</dl>\n</header>\n<main>\n");
+
+    for (index, hierarchical) in messages.iter().enumerate() {
+        let message = &hierarchical.message;
+        let role = message.get_role();
+        let anchor = message_anchor(&message.uuid);
+
+        let mut classes = format!("message {}", escape_html(role));
+        if hierarchical.is_initial {
+            classes.push_str(" initial");
+        }
+        out.push_str(&format!(
+            "<article class=\"{}\" id=\"{}\">\n<div class=\"meta\"><a href=\"#{}\">#{}</a><span class=\"role\">{}</span>",
+            classes,
+            anchor,
+            anchor,
+            index + 1,
+            escape_html(&role_title(role))
+        ));
+        if let Some(model) = message.get_model() {
+            out.push_str(&format!(
+                "<span class=\"model\">{}</span> ",
+                escape_html(model)
+            ));
+        }
+        out.push_str(&format!(
+            "<time datetime=\"{}\">{}</time></div>\n",
+            message.timestamp.to_rfc3339(),
+            format_timestamp(&message.timestamp)
+        ));
+
+        let blocks = message.content_blocks();
+        if blocks.is_empty() {
+            out.push_str(&format!(
+                "<div class=\"empty\">[{}]</div>\n",
+                escape_html(&message.msg_type)
+            ));
+        }
+
+        for block in blocks {
+            match block {
+                ContentBlock::Text(text) => {
+                    out.push_str(&format!(
+                        "<div class=\"text\">{}</div>\n",
+                        escape_html(text.trim_end())
+                    ));
+                }
+                ContentBlock::Thinking(thinking) => {
+                    out.push_str(&format!(
+                        "<details class=\"thinking\"><summary>Thinking</summary><div class=\"text\">{}</div></details>\n",
+                        escape_html(thinking.trim_end())
+                    ));
+                }
+                ContentBlock::ToolUse { name, input, .. } => {
+                    let input_str =
+                        serde_json::to_string_pretty(&input).unwrap_or_else(|_| input.to_string());
+                    out.push_str(&format!(
+                        "<details class=\"tool-use\"><summary>Tool: {}</summary><pre><code>{}</code></pre></details>\n",
+                        escape_html(&name),
+                        escape_html(&input_str)
+                    ));
+                }
+                ContentBlock::ToolResult {
+                    content, is_error, ..
+                } => {
+                    let (class, title) = if is_error {
+                        ("tool-result error", "Tool Result (error)")
+                    } else {
+                        ("tool-result", "Tool Result")
+                    };
+                    out.push_str(&format!(
+                        "<details class=\"{}\"><summary>{}</summary><pre><code>{}</code></pre></details>\n",
+                        class,
+                        title,
+                        escape_html(content.trim_end())
+                    ));
+                }
+                ContentBlock::Image(image) if !image.data.is_empty() => {
+                    out.push_str(&format!(
+                        "<img class=\"image\" src=\"data:{};base64,{}\" alt=\"{}\">\n",
+                        escape_html(&image.media_type),
+                        escape_html(image.data.trim()),
+                        escape_html(&image.placeholder())
+                    ));
+                }
+                ContentBlock::Image(image) => {
+                    out.push_str(&format!(
+                        "<div class=\"empty\">{}</div>\n",
+                        escape_html(&image.placeholder())
+                    ));
+                }
+            }
+        }
+
+        out.push_str("</article>\n");
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+pub fn render_chat(
+    format: ExportFormat,
+    project_name: &str,
+    chat_name: &str,
+    messages: &[HierarchicalMessage],
+) -> String {
+    match format {
+        ExportFormat::Markdown => chat_to_markdown(project_name, chat_name, messages),
+        ExportFormat::Html => chat_to_html(project_name, chat_name, messages),
+    }
+}
+
+/// Default export file name for a chat, in the current directory.
+pub fn default_export_path(chat_name: &str, format: ExportFormat) -> PathBuf {
+    PathBuf::from(format!("{}.{}", chat_name, format.extension()))
+}
+
+/// `path` if no file is there yet, otherwise the first free one of
+/// `<stem> (2).<ext>`, `<stem> (3).<ext>` and so on, so nothing gets overwritten.
+pub fn unused_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|number| path.with_file_name(format!("{} ({}){}", stem, number, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+pub fn write_export(
+    path: &Path,
+    format: ExportFormat,
+    project_name: &str,
+    chat_name: &str,
+    messages: &[HierarchicalMessage],
+) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, render_chat(format, project_name, chat_name, messages))?;
+    Ok(())
+}
+
+/// Decode images and write them to `dir` as `<stem>-<n>.<ext>`, next to
+/// files already there (see `unused_path`). Returns the paths written and
+/// how many of them got another name. Images given by URL have no data and
+/// are skipped.
+pub fn save_images(
+    images: &[ImageBlock],
+    dir: &Path,
+    stem: &str,
+) -> Result<(Vec<PathBuf>, usize), Box<dyn std::error::Error>> {
+    let mut paths = Vec::new();
+    let mut renamed = 0;
+    for (number, image) in images
+        .iter()
+        .filter(|image| !image.data.is_empty())
+        .enumerate()
+    {
+        let default_path = dir.join(format!("{}-{}.{}", stem, number + 1, image.extension()));
+        let path = unused_path(&default_path);
+        if path != default_path {
+            renamed += 1;
+        }
+        fs::write(&path, image.decode()?)?;
+        paths.push(path);
+    }
+    Ok((paths, renamed))
+}
+
+/// Load a chat from the projects directory and render it.
+pub fn export_chat(
+    projects_dir: &Path,
+    project_name: &str,
+    chat_name: &str,
+    format: ExportFormat,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let messages = load_messages(&chat_file_path(projects_dir, project_name, chat_name))?;
+    let messages = build_message_hierarchy(messages);
+    Ok(render_chat(format, project_name, chat_name, &messages))
+}
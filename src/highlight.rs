@@ -0,0 +1,174 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+const THEME_NAME: &str = "base16-ocean.dark";
+
+struct Assets {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+// Loading the bundled syntaxes takes a moment, so it's done once, on first use
+fn assets() -> &'static Assets {
+    static ASSETS: OnceLock<Assets> = OnceLock::new();
+    ASSETS.get_or_init(|| Assets {
+        syntaxes: SyntaxSet::load_defaults_newlines(),
+        theme: ThemeSet::load_defaults()
+            .themes
+            .remove(THEME_NAME)
+            .unwrap_or_default(),
+    })
+}
+
+/// Find a syntax from a code fence tag or a file extension.
+fn find_syntax<'a>(syntaxes: &'a SyntaxSet, token: &str) -> Option<&'a SyntaxReference> {
+    let token = token.to_lowercase();
+    // Common tags that don't match a bundled syntax's name or extensions
+    let token = match token.as_str() {
+        "shell" | "console" | "zsh" => "sh",
+        "ts" | "tsx" | "typescript" | "jsx" | "mjs" | "cjs" => "js",
+        "jsonc" | "json5" => "json",
+        "yml" => "yaml",
+        "py3" | "python3" => "py",
+        other => other,
+    };
+    syntaxes.find_syntax_by_token(token)
+}
+
+/// Language token (extension or file name) for a file path.
+pub fn language_from_path(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    path.extension()
+        .or_else(|| path.file_name())
+        .map(|token| token.to_string_lossy().to_string())
+        .filter(|token| find_syntax(&assets().syntaxes, token).is_some())
+}
+
+fn to_style(style: syntect::highlighting::Style) -> Style {
+    let color = style.foreground;
+    let mut result = Style::default().fg(Color::Rgb(color.r, color.g, color.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        result = result.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        result = result.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        result = result.add_modifier(Modifier::UNDERLINED);
+    }
+    result
+}
+
+/// Highlight code lines as the language named by `token`. Falls back to
+/// plain lines when the language is unknown.
+pub fn highlight_code(lines: &[&str], token: Option<&str>) -> Vec<Line<'static>> {
+    let plain = || {
+        lines
+            .iter()
+            .map(|line| Line::from(line.to_string()))
+            .collect()
+    };
+
+    let assets = assets();
+    let Some(syntax) = token.and_then(|token| find_syntax(&assets.syntaxes, token)) else {
+        return plain();
+    };
+
+    let mut highlighter = HighlightLines::new(syntax, &assets.theme);
+    let mut highlighted = Vec::with_capacity(lines.len());
+    for line in lines {
+        // The syntaxes expect lines to end with a newline
+        let line = format!("{}\n", line);
+        let Ok(ranges) = highlighter.highlight_line(&line, &assets.syntaxes) else {
+            return plain();
+        };
+        let spans: Vec<Span<'static>> = ranges
+            .into_iter()
+            .map(|(style, text)| {
+                Span::styled(text.trim_end_matches('\n').to_string(), to_style(style))
+            })
+            .collect();
+        highlighted.push(Line::from(spans));
+    }
+    highlighted
+}
+
+/// Split the line number prefix (`   12→`) of a line of file contents as
+/// returned by the Read tool.
+fn split_line_number(line: &str) -> (&str, &str) {
+    match line.split_once('→') {
+        Some((number, _))
+            if !number.trim().is_empty() && number.trim().chars().all(|c| c.is_ascii_digit()) =>
+        {
+            line.split_at(number.len() + '→'.len_utf8())
+        }
+        _ => ("", line),
+    }
+}
+
+/// Like `highlight_code`, but keeps line number prefixes out of the code.
+pub fn highlight_numbered_code(lines: &[&str], token: Option<&str>) -> Vec<Line<'static>> {
+    let (numbers, code): (Vec<&str>, Vec<&str>) =
+        lines.iter().map(|line| split_line_number(line)).unzip();
+    highlight_code(&code, token)
+        .into_iter()
+        .zip(numbers)
+        .map(|(mut line, number)| {
+            if !number.is_empty() {
+                line.spans.insert(
+                    0,
+                    Span::styled(number.to_string(), Style::default().fg(Color::DarkGray)),
+                );
+            }
+            line
+        })
+        .collect()
+}
+
+/// Lines of message text with fenced code blocks colored. `language_hint`
+/// is used for code blocks without a language tag. With `markdown`, the
+/// remaining text is rendered as Markdown.
+pub fn highlight_content(
+    content: &str,
+    language_hint: Option<&str>,
+    markdown: bool,
+) -> Vec<Line<'static>> {
+    let fence_style = Style::default().fg(Color::DarkGray);
+    let lines: Vec<&str> = content.lines().collect();
+    let mut result = Vec::with_capacity(lines.len());
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        // Fenced code block, only when it's closed
+        if let Some(tag) = line.trim_start().strip_prefix("```") {
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with("```"))
+                .map(|offset| i + 1 + offset);
+            if let Some(end) = end {
+                let tag = tag.split_whitespace().next();
+                result.push(Line::styled(line.to_string(), fence_style));
+                result.extend(highlight_code(&lines[i + 1..end], tag.or(language_hint)));
+                result.push(Line::styled(lines[end].to_string(), fence_style));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        result.push(if markdown {
+            crate::markdown::render_line(line)
+        } else {
+            Line::from(line.to_string())
+        });
+        i += 1;
+    }
+
+    result
+}
@@ -0,0 +1,375 @@
+use crate::project::{build_message_hierarchy, Message};
+use crate::search::{make_snippet, query_terms, tokenize, SearchHit};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const INDEX_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedMessage {
+    pub timestamp: DateTime<Utc>,
+    pub role: String,
+    /// Byte offset of the message's line in the chat file, which result
+    /// snippets are made from
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub project: String,
+    pub chat: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub mtime_nanos: u64,
+    /// Messages in hierarchy order, so positions match message numbers in the UI
+    pub messages: Vec<IndexedMessage>,
+    /// Token -> positions of the messages that contain it
+    pub postings: HashMap<String, Vec<u32>>,
+}
+
+/// One indexed chat file as saved, in a file of its own.
+#[derive(Serialize, Deserialize)]
+struct Shard<F> {
+    version: u32,
+    file: F,
+}
+
+/// Inverted index over the text of all chats under a projects directory.
+///
+/// Files are re-indexed only when their size or modification time changes,
+/// and each is saved on its own, so keeping the index up to date is cheap
+/// after the first build.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    /// Keyed by `<project>/<chat>.jsonl`
+    pub files: HashMap<String, IndexedFile>,
+    vocabulary: BTreeMap<String, Vec<String>>, // Token -> keys of files containing it
+    changed_keys: HashSet<String>,             // Not saved since they were indexed
+    removed_keys: HashSet<String>,             // Whose saved files are left to delete
+}
+
+/// FNV-1a hash. Unlike the standard library's hashers its output never
+/// changes, so file names derived from it stay the same across releases.
+fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Location of a cache in the user's cache directory, one per projects
+/// directory, named `<prefix>-<hash of the projects directory>`.
+pub fn cache_path(projects_dir: &Path, prefix: &str) -> Option<PathBuf> {
+    let hash = stable_hash(projects_dir.as_os_str().as_encoded_bytes());
+    dirs::cache_dir().map(|cache_dir| {
+        cache_dir
+            .join("cocohibo")
+            .join(format!("{}-{:016x}", prefix, hash))
+    })
+}
+
+/// Name of the file an indexed chat is saved in.
+fn shard_name(key: &str) -> String {
+    format!("{:016x}.json", stable_hash(key.as_bytes()))
+}
+
+impl SearchIndex {
+    /// Index directory in the user's cache directory, one per projects directory.
+    pub fn default_path(projects_dir: &Path) -> Option<PathBuf> {
+        cache_path(projects_dir, "index")
+    }
+
+    /// Load a saved index from its directory. Missing, unreadable or
+    /// outdated files are left out, to be indexed again by `update`.
+    pub fn load(dir: &Path) -> Self {
+        let mut index = SearchIndex::default();
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let shard = fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|content| serde_json::from_str::<Shard<IndexedFile>>(&content).ok())
+                .filter(|shard| shard.version == INDEX_VERSION);
+            if let Some(Shard { file, .. }) = shard {
+                index
+                    .files
+                    .insert(format!("{}/{}.jsonl", file.project, file.chat), file);
+            }
+        }
+        index.rebuild_vocabulary();
+        index
+    }
+
+    /// Write the files indexed since the last save and delete the ones of
+    /// removed chats.
+    pub fn save(&mut self, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(dir)?;
+        for key in std::mem::take(&mut self.removed_keys) {
+            let path = dir.join(shard_name(&key));
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+
+        let changed_keys: Vec<String> = self.changed_keys.iter().cloned().collect();
+        for key in changed_keys {
+            if let Some(file) = self.files.get(&key) {
+                let shard = Shard {
+                    version: INDEX_VERSION,
+                    file,
+                };
+                // Write to a temporary file first so a crash never leaves a truncated one
+                let path = dir.join(shard_name(&key));
+                let tmp_path = path.with_extension("json.tmp");
+                fs::write(&tmp_path, serde_json::to_string(&shard)?)?;
+                fs::rename(&tmp_path, path)?;
+            }
+            self.changed_keys.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// Bring the index in line with the chat files on disk. Returns whether
+    /// anything changed.
+    pub fn update(&mut self, projects_dir: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut changed = false;
+        let mut seen_keys = HashSet::new();
+
+        for project_entry in fs::read_dir(projects_dir)? {
+            let project_path = project_entry?.path();
+            if !project_path.is_dir() {
+                continue;
+            }
+            let project = project_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            for chat_entry in fs::read_dir(&project_path)? {
+                let chat_path = chat_entry?.path();
+                if !(chat_path.is_file() && chat_path.extension().is_some_and(|ext| ext == "jsonl"))
+                {
+                    continue;
+                }
+                let chat = chat_path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+
+                let metadata = fs::metadata(&chat_path)?;
+                let size = metadata.len();
+                let mtime_nanos = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0);
+
+                let key = format!("{}/{}.jsonl", project, chat);
+                seen_keys.insert(key.clone());
+
+                let up_to_date = self
+                    .files
+                    .get(&key)
+                    .is_some_and(|file| file.size == size && file.mtime_nanos == mtime_nanos);
+                if up_to_date {
+                    continue;
+                }
+
+                let mut indexed = index_chat(&chat_path, size, mtime_nanos);
+                indexed.project = project.clone();
+                indexed.chat = chat;
+                self.files.insert(key.clone(), indexed);
+                self.changed_keys.insert(key);
+                changed = true;
+            }
+        }
+
+        let removed: Vec<String> = self
+            .files
+            .keys()
+            .filter(|key| !seen_keys.contains(*key))
+            .cloned()
+            .collect();
+        for key in removed {
+            self.files.remove(&key);
+            self.changed_keys.remove(&key);
+            self.removed_keys.insert(key);
+            changed = true;
+        }
+
+        if changed {
+            self.rebuild_vocabulary();
+        }
+        Ok(changed)
+    }
+
+    fn rebuild_vocabulary(&mut self) {
+        self.vocabulary.clear();
+        for (key, file) in &self.files {
+            for token in file.postings.keys() {
+                self.vocabulary
+                    .entry(token.clone())
+                    .or_default()
+                    .push(key.clone());
+            }
+        }
+    }
+
+    /// Find messages containing every query term, matched like
+    /// `search::search_messages` does: as the start of an indexed token.
+    pub fn search(&self, project: Option<&str>, query: &str) -> Vec<SearchHit> {
+        let terms = query_terms(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        // For each term, the tokens it matches, which are next to each other
+        // in the sorted vocabulary
+        let term_tokens: Vec<Vec<&String>> = terms
+            .iter()
+            .map(|term| {
+                self.vocabulary
+                    .range::<String, _>(term..)
+                    .map(|(token, _)| token)
+                    .take_while(|token| token.starts_with(term.as_str()))
+                    .collect()
+            })
+            .collect();
+        if term_tokens.iter().any(|tokens| tokens.is_empty()) {
+            return Vec::new();
+        }
+
+        // Only files that contain a match for every term are worth looking at
+        let mut candidate_keys: Option<HashSet<&String>> = None;
+        for tokens in &term_tokens {
+            let keys: HashSet<&String> = tokens
+                .iter()
+                .flat_map(|token| self.vocabulary[*token].iter())
+                .collect();
+            candidate_keys = Some(match candidate_keys {
+                Some(candidates) => candidates.intersection(&keys).copied().collect(),
+                None => keys,
+            });
+        }
+
+        let mut files: Vec<&IndexedFile> = candidate_keys
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|key| self.files.get(key))
+            .filter(|file| project.is_none_or(|name| file.project == name))
+            .collect();
+        // Same order as the chat lists: by project, newest chats first
+        files.sort_by(|a, b| {
+            a.project
+                .cmp(&b.project)
+                .then(b.mtime_nanos.cmp(&a.mtime_nanos))
+        });
+
+        let mut hits = Vec::new();
+        for file in files {
+            let mut positions: Option<HashSet<u32>> = None;
+            for tokens in &term_tokens {
+                let term_positions: HashSet<u32> = tokens
+                    .iter()
+                    .filter_map(|token| file.postings.get(*token))
+                    .flatten()
+                    .copied()
+                    .collect();
+                positions = Some(match positions {
+                    Some(current) => current.intersection(&term_positions).copied().collect(),
+                    None => term_positions,
+                });
+            }
+
+            let mut positions: Vec<u32> = positions.unwrap_or_default().into_iter().collect();
+            if positions.is_empty() {
+                continue;
+            }
+            positions.sort_unstable();
+            // Snippets come from the chat file, only read for the matching messages
+            let mut chat_file = fs::File::open(&file.path).ok().map(BufReader::new);
+            for position in positions {
+                let Some(message) = file.messages.get(position as usize) else {
+                    continue;
+                };
+                let text = chat_file
+                    .as_mut()
+                    .and_then(|chat_file| read_message_text(chat_file, message.offset))
+                    .unwrap_or_default();
+                hits.push(SearchHit {
+                    project: file.project.clone(),
+                    chat: file.chat.clone(),
+                    message_index: position as usize,
+                    timestamp: message.timestamp,
+                    role: message.role.clone(),
+                    snippet: make_snippet(&text, &terms[0]),
+                });
+            }
+        }
+
+        hits
+    }
+}
+
+/// Searched text of the message on the line at `offset`.
+fn read_message_text(chat_file: &mut BufReader<fs::File>, offset: u64) -> Option<String> {
+    chat_file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut line = Vec::new();
+    chat_file.read_until(b'\n', &mut line).ok()?;
+    let message: Message = serde_json::from_slice(&line).ok()?;
+    Some(message.get_detailed_content())
+}
+
+fn index_chat(chat_path: &Path, size: u64, mtime_nanos: u64) -> IndexedFile {
+    let mut indexed = IndexedFile {
+        project: String::new(),
+        chat: String::new(),
+        path: chat_path.to_path_buf(),
+        size,
+        mtime_nanos,
+        messages: Vec::new(),
+        postings: HashMap::new(),
+    };
+
+    // Unparseable chats are kept as empty entries so they aren't retried until they change
+    let Ok(content) = fs::read_to_string(chat_path) else {
+        return indexed;
+    };
+
+    // Lines are parsed like `load_messages` does, remembering where each message is
+    let mut messages = Vec::new();
+    let mut offsets = HashMap::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if !line.trim().is_empty() {
+            if let Ok(message) = serde_json::from_str::<Message>(line) {
+                offsets.insert(message.uuid.clone(), offset as u64);
+                messages.push(message);
+            }
+        }
+        offset += line.len();
+    }
+
+    for (position, hierarchical) in build_message_hierarchy(messages).iter().enumerate() {
+        let message = &hierarchical.message;
+        let tokens: HashSet<String> = tokenize(&message.get_detailed_content()).collect();
+        for token in tokens {
+            indexed
+                .postings
+                .entry(token)
+                .or_default()
+                .push(position as u32);
+        }
+        indexed.messages.push(IndexedMessage {
+            timestamp: message.timestamp,
+            role: message.get_role().to_string(),
+            offset: offsets.get(&message.uuid).copied().unwrap_or_default(),
+        });
+    }
+
+    indexed
+}
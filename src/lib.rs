@@ -1,7 +1,18 @@
 pub mod app;
+pub mod commands;
 pub mod events;
+pub mod export;
+pub mod highlight;
+pub mod index;
+pub mod markdown;
+pub mod matcher;
 pub mod project;
+pub mod query;
+pub mod search;
+pub mod stats;
+pub mod tools;
 pub mod ui;
+pub mod viewer;
 
 #[cfg(test)]
 mod tests {
@@ -28,16 +39,19 @@ mod tests {
                 name: "project1".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "project2".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "project3".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
         ];
         app.projects.select(Some(2));
@@ -57,16 +71,19 @@ mod tests {
                 name: "project1".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "project2".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "project3".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
         ];
         app.projects.select(Some(0));
@@ -84,6 +101,7 @@ mod tests {
                 name: format!("project{}", i),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             })
             .collect();
         app.projects.select(Some(0));
@@ -103,6 +121,7 @@ mod tests {
                 name: format!("project{}", i),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             })
             .collect();
         app.projects.select(Some(5));
@@ -122,6 +141,7 @@ mod tests {
                 name: format!("project{}", i),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             })
             .collect();
         app.projects.select(Some(2));
@@ -279,6 +299,1569 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_nested_assistant_metadata() {
+        let test_chat =
+            std::path::Path::new("tests/sample-projects/test-project-1/basic-conversation.jsonl");
+        let messages =
+            super::project::load_messages(test_chat).expect("Should parse test messages");
+
+        // Model, usage and stop reason only live inside the nested message object here
+        let assistant_msg = messages
+            .iter()
+            .find(|m| m.get_role() == "assistant")
+            .expect("Should have an assistant message");
+        assert!(assistant_msg.model.is_none());
+        assert_eq!(
+            assistant_msg.get_model(),
+            Some("claude-3-5-sonnet-20241022")
+        );
+        assert_eq!(assistant_msg.get_stop_reason(), Some("end_turn"));
+        assert_eq!(
+            assistant_msg.get_api_message_id(),
+            Some("msg_assistant_001")
+        );
+
+        let usage = assistant_msg.get_usage().expect("Should have nested usage");
+        assert_eq!(usage.input_tokens, Some(12));
+        assert_eq!(usage.output_tokens, Some(25));
+
+        // User messages have no assistant metadata
+        let user_msg = messages.iter().find(|m| m.get_role() == "user").unwrap();
+        assert!(user_msg.get_model().is_none());
+        assert!(user_msg.get_usage().is_none());
+    }
+
+    #[test]
+    fn test_chat_usage_stats() {
+        let test_chat =
+            std::path::Path::new("tests/sample-projects/test-project-1/tool-usage-example.jsonl");
+        let mut messages =
+            super::project::load_messages(test_chat).expect("Should parse tool usage messages");
+
+        // A repeated line of the same API response must not be counted twice
+        let duplicate = messages
+            .iter()
+            .find(|m| m.get_usage().is_some())
+            .cloned()
+            .unwrap();
+        messages.push(duplicate);
+
+        let prices = super::stats::PriceTable::default();
+        let stats = super::stats::compute_chat_stats(&messages, &prices);
+
+        assert_eq!(stats.by_model.len(), 1);
+        assert_eq!(stats.by_model[0].model, "claude-sonnet-4-20250514");
+        assert_eq!(stats.by_model[0].responses, 3);
+        assert_eq!(stats.total.input, 465);
+        assert_eq!(stats.total.output, 309);
+        assert_eq!(stats.total.cache_creation, 1000);
+        assert_eq!(stats.total.cache_read, 0);
+        assert!(!stats.has_unpriced_models);
+        assert!((stats.total_cost - 0.00978).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_price_table_longest_pattern_wins() {
+        let mut prices = super::stats::PriceTable::default();
+        prices.prices.insert(
+            "claude-3-5-haiku".to_string(),
+            super::stats::ModelPrice {
+                input: 1.0,
+                output: 5.0,
+                cache_write: 0.0,
+                cache_read: 0.0,
+            },
+        );
+
+        let haiku = prices.price_for("claude-3-5-haiku-20241022").unwrap();
+        assert_eq!(haiku.input, 1.0);
+        let opus = prices.price_for("claude-opus-4-20250514").unwrap();
+        assert_eq!(opus.input, 15.0);
+        assert!(prices.price_for("<synthetic>").is_none());
+
+        assert_eq!(super::stats::format_count(1234567), "1,234,567");
+        assert_eq!(super::stats::format_count(999), "999");
+        assert_eq!(super::stats::format_count(123456), "123,456");
+    }
+
+    #[test]
+    fn test_project_usage_aggregation() {
+        let projects = super::project::discover_projects(
+            std::path::Path::new("tests/sample-projects"),
+            &mut super::project::ChatTotalsCache::default(),
+        )
+        .expect("Should discover sample projects");
+        let project = projects
+            .iter()
+            .find(|p| p.name == "test-project-1")
+            .expect("Should find test-project-1");
+
+        assert_eq!(project.chat_count, 3);
+        assert_eq!(project.message_count, 17);
+        assert_eq!(project.tokens.input, 12 + 45 + 465);
+        assert_eq!(project.tokens.output, 25 + 142 + 309);
+        assert_eq!(project.tokens.cache_creation, 1000);
+
+        // Messages without timestamps must not pull the range back to the epoch
+        let first = project.first_activity.expect("Should have first activity");
+        assert_eq!(first.to_rfc3339(), "2025-01-15T10:00:00+00:00");
+        assert!(project.last_activity.unwrap() > first);
+
+        let mut app = App::new(PathBuf::from("tests/sample-projects"), false);
+        app.load_projects().unwrap();
+        let (message_count, tokens) = app.global_totals();
+        assert_eq!(
+            message_count,
+            projects.iter().map(|p| p.message_count).sum::<usize>()
+        );
+        assert_eq!(tokens.input, 12 + 45 + 465 + 25 + 75);
+    }
+
+    #[test]
+    fn test_chat_totals_cache() {
+        use super::project::{load_project, ChatTotalsCache};
+
+        let dir = std::env::temp_dir().join(format!("cocohibo-test-totals-{}", std::process::id()));
+        let project_dir = dir.join("project");
+        let chat_path = project_dir.join("chat.jsonl");
+        let cache_path = dir.join("totals.json");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::copy(
+            "tests/sample-projects/test-project-1/basic-conversation.jsonl",
+            &chat_path,
+        )
+        .unwrap();
+
+        let mut cache = ChatTotalsCache::load(&cache_path);
+        let project = load_project(&project_dir, &mut cache).unwrap();
+        assert!(cache.is_changed());
+        cache.save(&cache_path).unwrap();
+
+        // Unchanged chats aren't parsed again, even by a reloaded cache
+        let mut cache = ChatTotalsCache::load(&cache_path);
+        let reloaded = load_project(&project_dir, &mut cache).unwrap();
+        assert!(!cache.is_changed());
+        assert_eq!(reloaded.message_count, project.message_count);
+        assert_eq!(reloaded.tokens, project.tokens);
+
+        // A chat that grew is
+        let mut content = std::fs::read_to_string(&chat_path).unwrap();
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(
+            r#"{"type":"user","uuid":"extra","timestamp":"2025-02-01T00:00:00Z","message":{"role":"user","content":"More"}}"#,
+        );
+        content.push('\n');
+        std::fs::write(&chat_path, content).unwrap();
+        let grown = load_project(&project_dir, &mut cache).unwrap();
+        assert!(cache.is_changed());
+        assert_eq!(grown.message_count, project.message_count + 1);
+        assert_eq!(
+            grown.last_activity.unwrap().to_rfc3339(),
+            "2025-02-01T00:00:00+00:00"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_search_across_chats() {
+        let projects_dir = std::path::Path::new("tests/sample-projects");
+
+        // Global search finds matches in every project
+        let hits = super::search::search_messages(projects_dir, None, "memory LEAK")
+            .expect("Should search all projects");
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|h| h.project == "debugging-session"));
+        assert!(hits[0].snippet.to_lowercase().contains("memory leak"));
+
+        // Every term must match, and the project scope is respected
+        let hits =
+            super::search::search_messages(projects_dir, Some("test-project-1"), "factorial")
+                .expect("Should search one project");
+        assert!(hits.iter().all(|h| h.chat == "tool-usage-example"));
+        assert!(hits.len() >= 2);
+        let hits =
+            super::search::search_messages(projects_dir, Some("test-project-1"), "factorial leak")
+                .unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_open_search_hit() {
+        let mut app = App::new(PathBuf::from("tests/sample-projects"), false);
+        app.load_projects().unwrap();
+        // An index that can't be saved still answers, and says so
+        let blocker = std::env::temp_dir().join(format!(
+            "cocohibo-test-index-blocker-{}",
+            std::process::id()
+        ));
+        std::fs::write(&blocker, "").unwrap();
+        app.index_path = Some(blocker.join("index"));
+
+        app.enter_content_search_mode();
+        assert!(app.content_search_mode);
+        assert_eq!(app.content_search_scope, None);
+        for c in "pandas".chars() {
+            app.add_to_content_search_query(c);
+        }
+        app.run_content_search().unwrap();
+        assert_eq!(app.screen, super::app::Screen::SearchResults);
+        assert!(!app.search_results.is_empty());
+        assert!(app
+            .status_message
+            .as_deref()
+            .unwrap()
+            .starts_with("Saving the search index failed"));
+        let _ = std::fs::remove_file(&blocker);
+
+        let hit = app.search_results.selected_item().cloned().unwrap();
+        app.open_search_hit().unwrap();
+        assert_eq!(app.screen, super::app::Screen::Messages);
+        assert_eq!(app.current_chat.as_ref().unwrap().name, hit.chat);
+        assert_eq!(app.messages.selected(), Some(hit.message_index));
+        assert!(app
+            .selected_message()
+            .unwrap()
+            .message
+            .get_content_text()
+            .contains("pandas"));
+
+        // Going back returns to the results, then to where the search started
+        app.go_back();
+        assert_eq!(app.screen, super::app::Screen::SearchResults);
+        app.go_back();
+        assert_eq!(app.screen, super::app::Screen::Projects);
+
+        // With tool results hidden, hits map to their filtered position or
+        // say they are hidden
+        app.hidden_categories
+            .insert(super::project::MessageCategory::ToolResult);
+        app.enter_content_search_mode();
+        for c in "120".chars() {
+            app.add_to_content_search_query(c);
+        }
+        app.run_content_search().unwrap();
+        let hits = app.search_results.items.clone();
+        let position = |message_index: usize| {
+            hits.iter()
+                .position(|hit| {
+                    hit.chat == "tool-usage-example" && hit.message_index == message_index
+                })
+                .unwrap()
+        };
+
+        app.search_results.select(Some(position(6)));
+        app.open_search_hit().unwrap();
+        assert_eq!(app.messages.selected(), Some(4));
+        assert_eq!(
+            app.selected_message().unwrap().message.uuid,
+            app.messages.items[6].message.uuid
+        );
+
+        app.go_back();
+        app.search_results.select(Some(position(5)));
+        app.open_search_hit().unwrap();
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Matching message is hidden by the filter")
+        );
+    }
+
+    #[test]
+    fn test_search_index() {
+        let projects_dir = std::path::Path::new("tests/sample-projects");
+        let index_path =
+            std::env::temp_dir().join(format!("cocohibo-test-index-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&index_path);
+
+        let mut index = super::index::SearchIndex::load(&index_path);
+        assert!(
+            index.update(projects_dir).unwrap(),
+            "First update indexes all chats"
+        );
+        assert!(
+            !index.update(projects_dir).unwrap(),
+            "Unchanged files are skipped"
+        );
+        index.save(&index_path).unwrap();
+
+        // Each chat is saved in a file of its own, so only a missing one is
+        // indexed again
+        let shards: Vec<PathBuf> = std::fs::read_dir(&index_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(shards.len(), index.files.len());
+        std::fs::remove_file(&shards[0]).unwrap();
+        let mut index = super::index::SearchIndex::load(&index_path);
+        assert_eq!(index.files.len(), shards.len() - 1);
+        assert!(index.update(projects_dir).unwrap());
+        index.save(&index_path).unwrap();
+        assert!(shards[0].exists());
+
+        // A reloaded index answers the same as a full scan, also for queries
+        // with punctuation and partial words
+        let index = super::index::SearchIndex::load(&index_path);
+        for query in ["memory leak", "memory.LEAK", "factor", "import pandas"] {
+            let indexed_hits = index.search(None, query);
+            let scanned_hits = super::search::search_messages(projects_dir, None, query).unwrap();
+            assert!(!indexed_hits.is_empty(), "No hits for {}", query);
+            assert_eq!(indexed_hits, scanned_hits, "Different hits for {}", query);
+        }
+
+        // Terms match the start of words, and the project scope is respected
+        assert!(!index.search(Some("test-project-1"), "factor").is_empty());
+        assert!(index.search(Some("test-project-1"), "actorial").is_empty());
+
+        // Snippets come from the searched text, so they show the match
+        let hits = index.search(None, "pandas");
+        assert!(!hits.is_empty());
+        assert!(hits
+            .iter()
+            .all(|hit| hit.snippet.to_lowercase().contains("pandas")));
+        assert!(index
+            .search(Some("debugging-session"), "factorial")
+            .is_empty());
+        assert!(index.search(None, "factorial nonexistentword").is_empty());
+
+        let _ = std::fs::remove_dir_all(&index_path);
+
+        // The directory name only depends on the projects directory
+        if let Some(path) = super::index::SearchIndex::default_path(projects_dir) {
+            assert!(path.ends_with("cocohibo/index-30c0449692fe80ba"));
+        }
+    }
+
+    #[test]
+    fn test_export_chat_to_markdown() {
+        let markdown = super::export::export_chat(
+            std::path::Path::new("tests/sample-projects"),
+            "test-project-1",
+            "tool-usage-example",
+            super::export::ExportFormat::Markdown,
+        )
+        .expect("Should export sample chat");
+
+        assert!(markdown.starts_with("# tool-usage-example\n"));
+        assert!(markdown.contains("- **Git Branch:** `feature-branch`"));
+        assert!(markdown.contains("## 1. User - 2025-01-15 14:00:00 UTC"));
+        assert!(markdown.contains("## 2. Assistant (claude-sonnet-4-20250514)"));
+        assert!(markdown.contains("<details>\n<summary>Thinking</summary>"));
+        assert!(markdown.contains("**Tool: Write**\n\n```json\n{"));
+        assert!(markdown.contains("**Tool Result**\n\n```\nFile created successfully"));
+
+        // Code blocks in assistant text are kept as Markdown
+        let markdown = super::export::export_chat(
+            std::path::Path::new("tests/sample-projects"),
+            "test-project-1",
+            "basic-conversation",
+            super::export::ExportFormat::Markdown,
+        )
+        .unwrap();
+        assert!(markdown.contains("```python\nimport pandas as pd"));
+
+        // Code fences inside tool output must not close the surrounding fence
+        let message = serde_json::from_value(serde_json::json!({
+            "type": "user",
+            "uuid": "result",
+            "timestamp": "2025-01-15T10:00:00Z",
+            "message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "```\ncode\n```"},
+            ]},
+        }))
+        .unwrap();
+        let messages = super::project::build_message_hierarchy(vec![message]);
+        let markdown = super::export::chat_to_markdown("project", "chat", &messages);
+        assert!(markdown.contains("````\n```\ncode\n```\n````"));
+    }
+
+    #[test]
+    fn test_export_keeps_existing_files() {
+        use super::export::unused_path;
+
+        let dir = std::env::temp_dir().join(format!("cocohibo-test-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("chat.md");
+        assert_eq!(unused_path(&path), path);
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(unused_path(&path), dir.join("chat (2).md"));
+        std::fs::write(dir.join("chat (2).md"), "").unwrap();
+        assert_eq!(unused_path(&path), dir.join("chat (3).md"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_chat_to_html() {
+        let html = super::export::export_chat(
+            std::path::Path::new("tests/sample-projects"),
+            "test-project-1",
+            "tool-usage-example",
+            super::export::ExportFormat::Html,
+        )
+        .expect("Should export sample chat");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<dt>Git Branch</dt><dd>feature-branch</dd>"));
+        assert!(html.contains("<dt>Models</dt><dd>claude-sonnet-4-20250514</dd>"));
+        assert!(html.contains("id=\"msg-msg-tool-002\""));
+        assert!(html.contains("<details class=\"thinking\"><summary>Thinking</summary>"));
+        assert!(html.contains("<summary>Tool: Write</summary>"));
+        // Content is escaped
+        assert!(html.contains("if n &lt; 0:"));
+        assert!(!html.contains("if n < 0:"));
+
+        assert_eq!(
+            super::export::escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!(super::export::message_anchor("a b/c"), "msg-a-b-c");
+    }
+
+    #[test]
+    fn test_cli_commands() {
+        let projects_dir = std::path::Path::new("tests/sample-projects");
+
+        let projects = super::commands::list_projects(projects_dir, None, false).unwrap();
+        assert_eq!(projects.lines().count(), 2);
+        assert!(projects
+            .lines()
+            .any(|line| line.starts_with("test-project-1\t") && line.contains("\t3\t17\t")));
+
+        let chats =
+            super::commands::list_chats(projects_dir, "test-project-1", None, true).unwrap();
+        let chats: serde_json::Value = serde_json::from_str(&chats).unwrap();
+        assert_eq!(chats.as_array().unwrap().len(), 3);
+
+        let chat =
+            super::commands::show_chat(projects_dir, "test-project-1", "basic-conversation", false)
+                .unwrap();
+        assert!(chat.starts_with("#1\tuser\t2025-01-15 10:00:00 UTC\nHello, can you help"));
+        assert!(
+            chat.contains("#4\tassistant\t2025-01-15 10:01:15 UTC\tclaude-3-5-sonnet-20241022\n")
+        );
+
+        let hits = super::commands::search(projects_dir, None, "pandas", None, false).unwrap();
+        assert!(hits
+            .lines()
+            .all(|line| line.starts_with("test-project-1\tbasic-conversation\t")));
+        let hits = super::commands::search(projects_dir, None, "pandas", None, true).unwrap();
+        let hits: Vec<super::search::SearchHit> = serde_json::from_str(&hits).unwrap();
+        assert!(!hits.is_empty());
+    }
+
+    #[test]
+    fn test_follow_appended_messages() {
+        use std::io::Write;
+
+        let projects_dir =
+            std::env::temp_dir().join(format!("cocohibo-test-follow-{}", std::process::id()));
+        let project_dir = projects_dir.join("live-project");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let chat_path = project_dir.join("live-chat.jsonl");
+        let line = |uuid: &str, parent: Option<&str>, text: &str| {
+            serde_json::json!({
+                "type": "user",
+                "uuid": uuid,
+                "parentUuid": parent,
+                "timestamp": "2025-01-15T10:00:00Z",
+                "message": {"role": "user", "content": text},
+            })
+            .to_string()
+        };
+        std::fs::write(&chat_path, format!("{}\n", line("m1", None, "first"))).unwrap();
+
+        let mut app = App::new(projects_dir.clone(), false);
+        app.load_projects().unwrap();
+        app.open_project().unwrap();
+        app.open_chat().unwrap();
+        assert_eq!(app.messages.len(), 1);
+
+        app.toggle_follow_mode();
+        assert!(!app.poll_chat_file().unwrap(), "Nothing was appended yet");
+
+        // A complete line and one that's still being written
+        let second = line("m2", Some("m1"), "second");
+        let third = line("m3", Some("m2"), "third");
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&chat_path)
+            .unwrap();
+        write!(file, "{}\n{}", second, &third[..10]).unwrap();
+        assert!(app.poll_chat_file().unwrap());
+        assert_eq!(app.messages.len(), 2);
+        assert_eq!(app.messages.selected(), Some(0), "Selection stays put");
+
+        // Once the line is finished it's picked up, and pinning follows it
+        app.toggle_follow_pinned();
+        writeln!(file, "{}", &third[10..]).unwrap();
+        assert!(app.poll_chat_file().unwrap());
+        assert_eq!(app.messages.len(), 3);
+        assert_eq!(app.messages.selected(), Some(2));
+        assert_eq!(app.selected_message().unwrap().message.uuid, "m3");
+        assert_eq!(app.selected_message().unwrap().chain_depth, 2);
+
+        // Reopened while pinned, the chat starts at its newest message
+        app.go_back();
+        app.open_chat().unwrap();
+        assert!(app.follow_mode && app.follow_pinned);
+        assert_eq!(app.messages.selected(), Some(2));
+
+        // A poll between two writes splitting a character loses nothing
+        let fourth = line("m4", Some("m3"), "café ☕");
+        let split = fourth.find('☕').unwrap() + 1;
+        file.write_all(&fourth.as_bytes()[..split]).unwrap();
+        assert!(!app.poll_chat_file().unwrap());
+        file.write_all(&fourth.as_bytes()[split..]).unwrap();
+        assert!(!app.poll_chat_file().unwrap(), "Not until the line ends");
+        writeln!(file).unwrap();
+        assert!(app.poll_chat_file().unwrap());
+        assert_eq!(
+            app.selected_message().unwrap().message.get_content_text(),
+            "café ☕"
+        );
+
+        let _ = std::fs::remove_dir_all(&projects_dir);
+    }
+
+    #[test]
+    fn test_refresh_lists() {
+        let projects_dir =
+            std::env::temp_dir().join(format!("cocohibo-test-refresh-{}", std::process::id()));
+        let write_chat = |project: &str, chat: &str| {
+            let project_dir = projects_dir.join(project);
+            std::fs::create_dir_all(&project_dir).unwrap();
+            let line = serde_json::json!({
+                "type": "user",
+                "uuid": format!("{}-{}", project, chat),
+                "timestamp": "2025-01-15T10:00:00Z",
+                "message": {"role": "user", "content": "hello"},
+            });
+            std::fs::write(
+                project_dir.join(format!("{}.jsonl", chat)),
+                format!("{}\n", line),
+            )
+            .unwrap();
+        };
+        write_chat("alpha", "chat-1");
+        write_chat("beta", "chat-1");
+
+        let mut app = App::new(projects_dir.clone(), false);
+        app.load_projects().unwrap();
+        assert!(!app.refresh_lists().unwrap(), "Nothing changed yet");
+
+        app.enter_search_mode();
+        for c in "bet".chars() {
+            app.add_to_search_query(c);
+        }
+        app.exit_search_mode_keep_filter();
+        assert_eq!(app.selected_project().unwrap().name, "beta");
+
+        // New projects show up, the filter and selection are kept
+        write_chat("gamma", "chat-1");
+        assert!(app.refresh_lists().unwrap());
+        assert_eq!(app.projects.items.len(), 3);
+        assert_eq!(app.projects.len(), 1);
+        assert_eq!(app.selected_project().unwrap().name, "beta");
+
+        // New chats of the open project show up too
+        app.open_project().unwrap();
+        write_chat("beta", "chat-2");
+        assert!(app.refresh_lists().unwrap());
+        assert_eq!(app.chats.len(), 2);
+        assert_eq!(app.current_project.as_ref().unwrap().chat_count, 2);
+        assert_eq!(app.current_project.as_ref().unwrap().message_count, 2);
+        assert!(!app.refresh_lists().unwrap());
+
+        // A grown chat is counted again, the others come from the cache
+        let chat_path = projects_dir.join("beta").join("chat-1.jsonl");
+        let line = std::fs::read_to_string(&chat_path).unwrap();
+        std::fs::write(
+            &chat_path,
+            line.replace("beta-chat-1", "beta-chat-1b") + &line,
+        )
+        .unwrap();
+        assert!(app.refresh_lists().unwrap());
+        let counts: Vec<(String, usize)> = app
+            .chats
+            .items
+            .iter()
+            .map(|chat| (chat.name.clone(), chat.message_count))
+            .collect();
+        assert!(counts.contains(&("chat-1".to_string(), 2)));
+        assert!(counts.contains(&("chat-2".to_string(), 1)));
+
+        let _ = std::fs::remove_dir_all(&projects_dir);
+    }
+
+    #[test]
+    fn test_details_scrolling() {
+        let mut app = App::new(PathBuf::from("tests/sample-projects"), false);
+        app.toggle_focus();
+        assert!(
+            !app.details_focused(),
+            "Focus only moves on the messages screen"
+        );
+
+        app.screen = super::app::Screen::Messages;
+        app.toggle_focus();
+        assert!(app.details_focused());
+
+        app.set_details_layout(Some("m1"), 100, 20);
+        app.scroll_details_down(30);
+        assert_eq!(app.details_scroll, 30);
+        app.scroll_details_down(100);
+        assert_eq!(app.details_scroll, 80, "Can't scroll past the last page");
+        app.scroll_details_up(5);
+        assert_eq!(app.details_scroll, 75);
+
+        // A taller pane shows more lines, so there's less to scroll
+        app.set_details_layout(Some("m1"), 100, 40);
+        assert_eq!(app.details_scroll, 60);
+
+        app.set_details_layout(Some("m2"), 100, 40);
+        assert_eq!(app.details_scroll, 0, "Another message starts at the top");
+
+        app.go_back();
+        assert!(!app.details_focused());
+    }
+
+    #[test]
+    fn test_viewer_search() {
+        use super::viewer::Viewer;
+
+        let content = (1..=100)
+            .map(|i| {
+                if i % 25 == 0 {
+                    format!("line {} has an Error", i)
+                } else {
+                    format!("line {}", i)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut viewer = Viewer::new("test".to_string(), &content);
+        viewer.set_layout(80, 10);
+        viewer.go_to_bottom();
+        assert_eq!(viewer.scroll, 90);
+        viewer.go_to_top();
+
+        viewer.enter_search_mode();
+        for c in "error".chars() {
+            viewer.add_to_search_query(c);
+        }
+        viewer.confirm_search();
+        assert_eq!(viewer.matches.len(), 4);
+        assert_eq!(viewer.current_match, Some(0));
+        let first = viewer.matches[0];
+        assert_eq!(&viewer.lines[first.line][first.start..first.end], "Error");
+        // Line 25 is scrolled into view with some context above it
+        assert_eq!(viewer.scroll, 24 - 10 / 3);
+
+        viewer.previous_match();
+        assert_eq!(
+            viewer.current_match,
+            Some(3),
+            "Wraps around to the last match"
+        );
+        assert_eq!(viewer.scroll, 90);
+        viewer.next_match();
+        assert_eq!(viewer.current_match, Some(0));
+        assert_eq!(viewer.search_status().as_deref(), Some("/error: match 1/4"));
+
+        viewer.enter_search_mode();
+        viewer.add_to_search_query('x');
+        viewer.confirm_search();
+        assert!(viewer.matches.is_empty());
+        assert_eq!(
+            viewer.search_status().as_deref(),
+            Some("Pattern not found: x")
+        );
+
+        // Matches stay on character boundaries when lowercasing changes lengths
+        let mut viewer = Viewer::new("test".to_string(), "\u{130}\u{130}\u{212A} and K");
+        viewer.set_layout(80, 10);
+        viewer.enter_search_mode();
+        viewer.add_to_search_query('k');
+        let matched: Vec<&str> = viewer
+            .matches
+            .iter()
+            .map(|m| &viewer.lines[m.line][m.start..m.end])
+            .collect();
+        assert_eq!(matched, ["\u{212A}", "K"]);
+    }
+
+    #[test]
+    fn test_highlight_content() {
+        use super::highlight::{highlight_content, highlight_numbered_code, language_from_path};
+        use super::tools::{render_message_content, ToolCallIndex};
+        use ratatui::style::Color;
+
+        assert_eq!(language_from_path("src/main.rs").as_deref(), Some("rs"));
+        assert_eq!(language_from_path("notes.unknown-extension"), None);
+
+        let is_colored = |line: &ratatui::text::Line| {
+            line.spans
+                .iter()
+                .any(|span| matches!(span.style.fg, Some(Color::Rgb(..))))
+        };
+
+        let content =
+            "Here you go:\n```python\ndef f():\n    return 1\n```\n\n```\nfn main() {}\n```";
+        let lines = highlight_content(content, None, false);
+        assert_eq!(lines.len(), content.lines().count());
+        assert!(!is_colored(&lines[0]), "Prose stays plain");
+        assert!(is_colored(&lines[2]));
+        assert_eq!(lines[3].to_string(), "    return 1", "Indentation is kept");
+        assert!(!is_colored(&lines[7]), "No language, no colors");
+        let lines = highlight_content(content, Some("rs"), false);
+        assert!(is_colored(&lines[7]), "Unlabeled blocks use the hint");
+
+        // File contents with line numbers, which stay plain
+        let lines = highlight_numbered_code(&["     1→fn main() {}"], Some("rs"));
+        assert_eq!(lines[0].to_string(), "     1→fn main() {}");
+        assert_eq!(lines[0].spans[0].content, "     1→");
+        assert!(is_colored(&lines[0]));
+
+        // Tool parameters are colored as JSON
+        let messages = tool_exchange(
+            "Custom",
+            serde_json::json!({"cmd": "echo \"hi\"", "limit": 5, "options": {"depth": 2}}),
+            "Done",
+            false,
+        );
+        let index = ToolCallIndex::build(&messages);
+        let lines = render_message_content(&messages[0].message, &messages, &index, false);
+        let line = |text: &str| lines.iter().find(|line| line.to_string() == text).unwrap();
+        assert!(is_colored(line("  limit: 5")));
+        assert!(is_colored(line("      \"depth\": 2")));
+        assert!(is_colored(line(r#"  cmd: "echo \"hi\"""#)));
+
+        // Tool results are highlighted like the file, line numbers stay plain
+        let messages = tool_exchange(
+            "Read",
+            serde_json::json!({"file_path": "src/main.rs"}),
+            "     1→fn main() {}",
+            false,
+        );
+        let index = ToolCallIndex::build(&messages);
+        let lines = render_message_content(&messages[1].message, &messages, &index, false);
+        assert_eq!(lines[3].to_string(), "     1→fn main() {}");
+        assert_eq!(lines[3].spans[0].content, "     1→");
+        assert!(is_colored(&lines[3]));
+        let messages = tool_exchange(
+            "Glob",
+            serde_json::json!({"pattern": "*"}),
+            "fn main() {}",
+            false,
+        );
+        let index = ToolCallIndex::build(&messages);
+        let lines = render_message_content(&messages[1].message, &messages, &index, false);
+        assert!(!is_colored(&lines[3]));
+    }
+
+    /// A tool call and its result, 1.5s later.
+    fn tool_exchange(
+        name: &str,
+        input: serde_json::Value,
+        result: &str,
+        is_error: bool,
+    ) -> Vec<super::project::HierarchicalMessage> {
+        use super::project::{build_message_hierarchy, Message};
+
+        let messages: Vec<Message> = [
+            serde_json::json!({
+                "type": "assistant",
+                "uuid": "call",
+                "timestamp": "2025-01-15T10:00:00Z",
+                "message": {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "toolu_1", "name": name, "input": input},
+                ]},
+            }),
+            serde_json::json!({
+                "type": "user",
+                "uuid": "result",
+                "parentUuid": "call",
+                "timestamp": "2025-01-15T10:00:01.500Z",
+                "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1",
+                     "content": result, "is_error": is_error},
+                ]},
+            }),
+        ]
+        .into_iter()
+        .map(|value| serde_json::from_value(value).unwrap())
+        .collect();
+        build_message_hierarchy(messages)
+    }
+
+    /// A chat in which a prompt was retried, with a reply on each branch.
+    fn forked_conversation() -> Vec<super::project::HierarchicalMessage> {
+        let tree = [
+            ("q", None),
+            ("a1", Some("q")),
+            ("fork", Some("a1")),
+            ("b1", Some("fork")),
+            ("b1-reply", Some("b1")),
+            ("b2", Some("fork")),
+            ("b2-reply", Some("b2")),
+        ];
+        let messages = tree
+            .iter()
+            .enumerate()
+            .map(|(minute, (uuid, parent))| {
+                serde_json::from_value(serde_json::json!({
+                    "type": "user",
+                    "uuid": uuid,
+                    "parentUuid": parent,
+                    "timestamp": format!("2025-01-15T10:{:02}:00Z", minute),
+                    "message": {"role": "user", "content": uuid},
+                }))
+                .unwrap()
+            })
+            .collect();
+        super::project::build_message_hierarchy(messages)
+    }
+
+    #[test]
+    fn test_conversation_tree() {
+        use super::app::Screen;
+
+        let messages = forked_conversation();
+        let prefixes: Vec<&str> = messages.iter().map(|m| m.tree_prefix.as_str()).collect();
+        assert_eq!(
+            prefixes,
+            [
+                "",
+                "  ├─",
+                "  └─",
+                "    ├─",
+                "    │ └─",
+                "    └─",
+                "      └─"
+            ]
+        );
+        assert_eq!(messages[0].descendant_count, 6);
+        assert_eq!(messages[2].descendant_count, 4);
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = messages;
+        app.messages.select(Some(3));
+
+        // Collapsing hides the branch's reply
+        app.toggle_collapse();
+        assert_eq!(app.messages.len(), 6);
+        assert_eq!(app.selected_message().unwrap().message.uuid, "b1");
+
+        // Collapsing the fork hides both branches, numbering stays the same
+        app.messages.select(Some(2));
+        app.toggle_collapse();
+        assert_eq!(app.messages.len(), 3);
+        app.go_to_bottom();
+        assert_eq!(app.selected_message().unwrap().message.uuid, "fork");
+
+        // Search only looks at visible messages
+        app.enter_search_mode();
+        app.add_to_search_query('b');
+        app.add_to_search_query('2');
+        assert!(app
+            .messages
+            .active_items()
+            .iter()
+            .all(|m| !m.message.uuid.starts_with("b2")));
+        app.exit_search_mode();
+
+        app.expand_all();
+        assert_eq!(app.messages.len(), 7);
+        assert!(!app.messages.is_filtered());
+    }
+
+    #[test]
+    fn test_branch_navigation() {
+        use super::app::Screen;
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = forked_conversation();
+        let branches: Vec<_> = app.messages.items.iter().map(|m| m.branch).collect();
+        assert_eq!(
+            branches,
+            [None, None, None, Some((1, 2)), None, Some((2, 2)), None]
+        );
+
+        // The active path follows the newest branch
+        app.toggle_active_path();
+        let uuids = |app: &App| {
+            app.messages
+                .active_items()
+                .iter()
+                .map(|m| m.message.uuid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(uuids(&app), ["q", "a1", "fork", "b2", "b2-reply"]);
+
+        // Switching from inside a branch selects the other one
+        app.go_to_bottom();
+        app.switch_branch(true);
+        assert_eq!(uuids(&app), ["q", "a1", "fork", "b1", "b1-reply"]);
+        assert_eq!(app.selected_message().unwrap().message.uuid, "b1");
+        assert_eq!(app.status_message.as_deref(), Some("Branch 1/2"));
+
+        // Above the fork there's nothing to switch
+        app.go_to_top();
+        app.switch_branch(false);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("No alternate branches here")
+        );
+
+        // The whole tree again, the choice is remembered
+        app.toggle_active_path();
+        assert_eq!(app.messages.len(), 7);
+        app.toggle_active_path();
+        assert_eq!(uuids(&app)[3], "b1");
+    }
+
+    #[test]
+    fn test_sidechain_grouping() {
+        use super::app::{Screen, SidechainFilter};
+        use super::project::{build_message_hierarchy, Message};
+
+        let message = |uuid: &str, parent: Option<&str>, sidechain: bool, content| {
+            serde_json::from_value::<Message>(serde_json::json!({
+                "type": "user",
+                "uuid": uuid,
+                "parentUuid": parent,
+                "isSidechain": sidechain,
+                "timestamp": "2025-01-15T10:00:00Z",
+                "message": {"role": "user", "content": content},
+            }))
+            .unwrap()
+        };
+        let messages = vec![
+            message("q", None, false, serde_json::json!("Find the bug")),
+            message(
+                "task",
+                Some("q"),
+                false,
+                serde_json::json!([{"type": "tool_use", "id": "toolu_1", "name": "Task",
+                    "input": {"description": "Search", "prompt": "Look for the bug"}}]),
+            ),
+            message("result", Some("task"), false, serde_json::json!("Found it")),
+            // The subagent's run has no parent, it's matched by its prompt
+            message("agent", None, true, serde_json::json!("Look for the bug")),
+            message(
+                "agent-reply",
+                Some("agent"),
+                true,
+                serde_json::json!("In main.rs"),
+            ),
+        ];
+        let messages = build_message_hierarchy(messages);
+        let rows: Vec<(&str, &str)> = messages
+            .iter()
+            .map(|m| (m.message.uuid.as_str(), m.tree_prefix.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("q", ""),
+                ("task", "  ├─"),
+                ("agent", "  │ ⤷ "),
+                ("agent-reply", "  │ └─"),
+                ("result", "  └─"),
+            ]
+        );
+        assert_eq!(messages[1].descendant_count, 3);
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = messages;
+        app.messages.select(Some(0));
+
+        app.cycle_sidechain_filter();
+        assert_eq!(app.sidechain_filter, SidechainFilter::Hide);
+        assert_eq!(app.messages.len(), 3);
+        app.cycle_sidechain_filter();
+        assert_eq!(app.sidechain_filter, SidechainFilter::Only);
+        assert_eq!(app.messages.len(), 2);
+        assert_eq!(app.selected_message().unwrap().message.uuid, "agent");
+        app.cycle_sidechain_filter();
+        assert_eq!(app.messages.len(), 5);
+
+        // The group collapses into its first message
+        app.messages.select(Some(2));
+        app.toggle_collapse();
+        assert_eq!(app.messages.len(), 4);
+
+        // A retried Task call: each run is on the path of its own call, and a
+        // run matching no prompt goes to the same call every time
+        let task = |uuid: &str, prompt: &str| {
+            message(
+                uuid,
+                Some("q"),
+                false,
+                serde_json::json!([{"type": "tool_use", "id": uuid, "name": "Task",
+                    "input": {"description": "Search", "prompt": prompt}}]),
+            )
+        };
+        let messages = vec![
+            message("q", None, false, serde_json::json!("Find the bug")),
+            task("task", "Look for the bug"),
+            message("agent", None, true, serde_json::json!("Look for the bug")),
+            task("task2", "Look again"),
+            message("agent2", None, true, serde_json::json!("Look again")),
+            message("agent3", None, true, serde_json::json!("Something else")),
+        ];
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = build_message_hierarchy(messages);
+        app.toggle_active_path();
+        let uuids: Vec<&str> = app
+            .messages
+            .active_items()
+            .iter()
+            .map(|m| m.message.uuid.as_str())
+            .collect();
+        assert_eq!(uuids, ["q", "task2", "agent2", "agent3"]);
+    }
+
+    #[test]
+    fn test_message_category_toggles() {
+        use super::app::Screen;
+        use super::project::{build_message_hierarchy, Message, MessageCategory};
+
+        let message = |uuid: &str, role: &str, extra: serde_json::Value, content| {
+            let mut value = serde_json::json!({
+                "type": role,
+                "uuid": uuid,
+                "timestamp": "2025-01-15T10:00:00Z",
+                "message": {"role": role, "content": content},
+            });
+            value
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            serde_json::from_value::<Message>(value).unwrap()
+        };
+        let none = serde_json::json!({});
+        let tool_use =
+            serde_json::json!({"type": "tool_use", "id": "t1", "name": "Read", "input": {}});
+        let messages = vec![
+            message("prompt", "user", none.clone(), serde_json::json!("Hi")),
+            message(
+                "meta",
+                "user",
+                serde_json::json!({"isMeta": true}),
+                serde_json::json!("Caveat"),
+            ),
+            message(
+                "thinking",
+                "assistant",
+                none.clone(),
+                serde_json::json!([{"type": "thinking", "thinking": "Hmm"}]),
+            ),
+            message(
+                "text-and-call",
+                "assistant",
+                none.clone(),
+                serde_json::json!([{"type": "text", "text": "Reading"}, tool_use.clone()]),
+            ),
+            message(
+                "call",
+                "assistant",
+                none.clone(),
+                serde_json::json!([tool_use]),
+            ),
+            message(
+                "result",
+                "user",
+                none.clone(),
+                serde_json::json!([{"type": "tool_result", "tool_use_id": "t1", "content": "ok"}]),
+            ),
+            message("system", "system", none, serde_json::json!("Compacted")),
+        ];
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = build_message_hierarchy(messages);
+        app.messages.select(Some(0));
+        let visible = |app: &App| {
+            app.messages
+                .active_items()
+                .iter()
+                .map(|m| m.message.uuid.clone())
+                .collect::<Vec<_>>()
+        };
+
+        for category in [
+            MessageCategory::Meta,
+            MessageCategory::ToolCall,
+            MessageCategory::ToolResult,
+            MessageCategory::Thinking,
+            MessageCategory::System,
+        ] {
+            app.toggle_category(category);
+        }
+        assert_eq!(visible(&app), ["prompt", "text-and-call"]);
+        assert_eq!(app.status_message.as_deref(), Some("System hidden"));
+
+        app.toggle_category(MessageCategory::ToolCall);
+        assert_eq!(app.status_message.as_deref(), Some("Tool calls shown"));
+        assert_eq!(visible(&app), ["prompt", "text-and-call", "call"]);
+    }
+
+    #[test]
+    fn test_message_query() {
+        use super::app::Screen;
+        use super::project::{build_message_hierarchy, Message};
+        use super::query::{Comparison, Filter, MessageQuery};
+
+        let now = chrono::DateTime::parse_from_rfc3339("2025-01-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let query = MessageQuery::parse("fix -tool:Read tokens>=2k after:3d see http://x", now);
+        assert_eq!(query.text, "fix see http://x");
+        assert!(query.terms[0].negated);
+        assert_eq!(query.terms[0].filter, Filter::Tool("read".to_string()));
+        assert_eq!(
+            query.terms[1].filter,
+            Filter::Tokens(Comparison::GreaterOrEqual, 2000)
+        );
+        assert_eq!(
+            query.terms[2].filter,
+            Filter::After(now - chrono::Duration::days(3))
+        );
+
+        // Times too far back are free text instead of panicking
+        let query = MessageQuery::parse("after:100000000d after:9999999999999999h", now);
+        assert!(query.terms.is_empty());
+        assert_eq!(query.text, "after:100000000d after:9999999999999999h");
+
+        let call = |id: &str, tool: &str| serde_json::json!([{"type": "tool_use", "id": id, "name": tool, "input": {}}]);
+        let result = |id: &str, is_error: bool| {
+            serde_json::json!([{"type": "tool_result", "tool_use_id": id,
+                "content": "output", "is_error": is_error}])
+        };
+        let rows = [
+            (
+                "prompt",
+                "user",
+                "2025-01-10",
+                serde_json::json!("Fix the build"),
+            ),
+            ("bash", "assistant", "2025-01-18", call("t1", "Bash")),
+            ("bash-error", "user", "2025-01-18", result("t1", true)),
+            ("read", "assistant", "2025-01-19", call("t2", "Read")),
+            ("read-ok", "user", "2025-01-19", result("t2", false)),
+        ];
+        let messages: Vec<Message> = rows
+            .iter()
+            .map(|(uuid, role, day, content)| {
+                let mut inner = serde_json::json!({"role": role, "content": content});
+                if *role == "assistant" {
+                    inner["model"] = serde_json::json!("claude-sonnet-4");
+                    inner["usage"] =
+                        serde_json::json!({"input_tokens": 5000, "output_tokens": 100});
+                }
+                serde_json::from_value(serde_json::json!({
+                    "type": role,
+                    "uuid": uuid,
+                    "gitBranch": "main",
+                    "timestamp": format!("{}T10:00:00Z", day),
+                    "message": inner,
+                }))
+                .unwrap()
+            })
+            .collect();
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = build_message_hierarchy(messages);
+        app.tool_calls = super::tools::ToolCallIndex::build(&app.messages.items);
+        let mut search = |query: &str| {
+            app.apply_fuzzy_message_filter(query);
+            app.messages
+                .active_items()
+                .iter()
+                .map(|m| m.message.uuid.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(search("tool:bash has:error"), ["bash-error"]);
+        assert_eq!(search("role:assistant -tool:Read"), ["bash"]);
+        assert_eq!(search("model:sonnet tokens>5k"), ["bash", "read"]);
+        assert_eq!(search("branch:main before:2025-01-18"), ["prompt"]);
+        assert_eq!(search("has:result -has:error"), ["read-ok"]);
+        assert_eq!(search("build role:user"), ["prompt"]);
+    }
+
+    #[test]
+    fn test_match_modes() {
+        use super::app::Screen;
+        use super::matcher::MatchMode;
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = forked_conversation();
+        app.messages.select(Some(0));
+        let visible = |app: &App| {
+            app.messages
+                .active_items()
+                .iter()
+                .map(|m| m.message.uuid.clone())
+                .collect::<Vec<_>>()
+        };
+
+        app.enter_search_mode();
+        for c in "B1".chars() {
+            app.add_to_search_query(c);
+        }
+        assert_eq!(app.search_status(), "/B1  [fuzzy]");
+
+        app.cycle_match_mode();
+        assert_eq!(app.match_mode, MatchMode::Exact);
+        assert_eq!(visible(&app), ["b1", "b1-reply"]);
+
+        app.cycle_match_mode();
+        assert_eq!(app.match_mode, MatchMode::ExactCaseSensitive);
+        assert!(visible(&app).is_empty());
+
+        app.cycle_match_mode();
+        assert_eq!(app.match_mode, MatchMode::Regex);
+        app.remove_from_search_query();
+        app.remove_from_search_query();
+        for c in r"^b\d$".chars() {
+            app.add_to_search_query(c);
+        }
+        assert_eq!(visible(&app), ["b1", "b2"]);
+
+        // An unfinished pattern leaves the matches as they were
+        app.add_to_search_query('(');
+        assert_eq!(visible(&app), ["b1", "b2"]);
+        assert_eq!(app.search_status(), r"/^b\d$(  [regex: invalid pattern]");
+
+        // Message numbers don't match patterns, only the whole number
+        for _ in r"^b\d$(".chars() {
+            app.remove_from_search_query();
+        }
+        for c in r"\d".chars() {
+            app.add_to_search_query(c);
+        }
+        assert_eq!(visible(&app), ["a1", "b1", "b1-reply", "b2", "b2-reply"]);
+        app.remove_from_search_query();
+        app.remove_from_search_query();
+        app.add_to_search_query('3');
+        assert_eq!(visible(&app), ["fork"]);
+
+        app.cycle_match_mode();
+        assert_eq!(app.match_mode, MatchMode::Fuzzy);
+    }
+
+    #[test]
+    fn test_search_highlights() {
+        use super::app::Screen;
+        use super::matcher::{MatchMode, Matcher};
+
+        let fuzzy = Matcher::new(MatchMode::Fuzzy, "mcb").unwrap();
+        assert_eq!(fuzzy.highlights("my cool_branch"), [0..1, 3..4, 8..9]);
+        // Character indices of the match are turned into byte ranges
+        assert_eq!(fuzzy.highlights("é mxcb"), [3..4, 5..7]);
+        assert!(fuzzy.highlights("nothing").is_empty());
+
+        let fuzzy_words = Matcher::new(MatchMode::Fuzzy, "foo bar").unwrap();
+        assert_eq!(fuzzy_words.occurrences("Bar, foobar"), [0..3, 5..11]);
+
+        let exact = Matcher::new(MatchMode::Exact, "AB").unwrap();
+        assert_eq!(exact.highlights("ab xAb"), [0..2, 4..6]);
+        // Lowercasing makes `İ` longer and the Kelvin sign shorter, the ranges
+        // must still fall on character boundaries of the original text
+        let kelvin = Matcher::new(MatchMode::Exact, "k").unwrap();
+        assert_eq!(kelvin.highlights("\u{130}\u{130}\u{212A}k"), [4..7, 7..8]);
+        let dotted = Matcher::new(MatchMode::Exact, "i").unwrap();
+        assert_eq!(dotted.highlights("a\u{130}bI"), [1..3, 4..5]);
+        let case_sensitive = Matcher::new(MatchMode::ExactCaseSensitive, "AB").unwrap();
+        assert!(case_sensitive.highlights("ab xab").is_empty());
+        let regex = Matcher::new(MatchMode::Regex, r"b\d").unwrap();
+        assert_eq!(regex.highlights("b1 b2 bx"), [0..2, 3..5]);
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = forked_conversation();
+        app.messages.select(Some(0));
+
+        // Without a search there's nothing to jump to
+        app.jump_to_details_match(true);
+        assert_eq!(app.details_match, None);
+
+        app.enter_search_mode();
+        for c in "role:user b".chars() {
+            app.add_to_search_query(c);
+        }
+        app.exit_search_mode_keep_filter();
+        assert_eq!(app.selected_message().unwrap().message.uuid, "b1");
+
+        // The qualifier isn't highlighted, only the free text
+        let matches = app.details_matches();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].1, 0..1);
+
+        app.jump_to_details_match(true);
+        assert_eq!(app.details_match, Some(0));
+        assert_eq!(app.status_message.as_deref(), Some("Match 1/1"));
+        app.jump_to_details_match(false);
+        assert_eq!(app.details_match, Some(0));
+    }
+
+    #[test]
+    fn test_find_mode() {
+        use super::app::Screen;
+
+        let mut app = App::new(std::path::PathBuf::from("/tmp"), false);
+        app.screen = Screen::Messages;
+        app.messages.items = forked_conversation();
+        app.messages.select(Some(0));
+        let selected = |app: &App| app.selected_message().unwrap().message.uuid.clone();
+        let order: Vec<String> = app
+            .messages
+            .items
+            .iter()
+            .map(|m| m.message.uuid.clone())
+            .collect();
+
+        app.enter_find_mode();
+        for c in "reply".chars() {
+            app.add_to_find_query(c);
+        }
+        app.confirm_find();
+
+        // Nothing is filtered out or reordered
+        assert_eq!(app.messages.active_items().len(), order.len());
+        assert_eq!(app.messages.original_index(2), 2);
+        assert_eq!(app.find_matches.len(), 2);
+        assert_eq!(selected(&app), order[app.find_matches[0]]);
+        assert!(app.find_status().ends_with("match 1/2"));
+
+        app.jump_to_find_match(true);
+        assert_eq!(selected(&app), order[app.find_matches[1]]);
+        assert!(app.find_status().ends_with("match 2/2"));
+        app.jump_to_find_match(true);
+        assert_eq!(selected(&app), order[app.find_matches[0]]);
+        app.jump_to_find_match(false);
+        assert_eq!(selected(&app), order[app.find_matches[1]]);
+
+        // Away from the matches, only the count is shown
+        app.messages.select(Some(0));
+        assert!(app.find_status().ends_with("  2 matches"));
+
+        // Qualifiers narrow the find like a search
+        app.enter_find_mode();
+        for c in "role:user".chars() {
+            app.add_to_find_query(c);
+        }
+        assert!(app
+            .find_matches
+            .iter()
+            .all(|&index| app.messages.items[index].message.get_role() == "user"));
+
+        app.exit_find_mode();
+        assert!(!app.find_active());
+        app.jump_to_details_match(true);
+        assert_eq!(app.details_match, None);
+    }
+
+    #[test]
+    fn test_tool_call_pairing() {
+        use super::tools::{render_message_content, ToolCallIndex};
+
+        let messages = tool_exchange(
+            "Bash",
+            serde_json::json!({"command": "ls", "description": "List files"}),
+            "Cargo.toml\nsrc",
+            true,
+        );
+        let index = ToolCallIndex::build(&messages);
+        assert_eq!(index.counterpart(&messages[0].message), Some(1));
+        assert_eq!(index.counterpart(&messages[1].message), Some(0));
+
+        // Both halves show the call with its result
+        for message in &messages {
+            let text: Vec<String> =
+                render_message_content(&message.message, &messages, &index, false)
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect();
+            assert!(text.contains(&"▶ Tool: Bash".to_string()), "{:?}", text);
+            assert!(text.contains(&"  $ ls".to_string()));
+            assert!(text.contains(&"◀ Output (exit status error), 1.5s".to_string()));
+            assert!(text.contains(&"Cargo.toml".to_string()));
+        }
+
+        let mut app = App::new(PathBuf::from("/tmp"), false);
+        app.screen = super::app::Screen::Messages;
+        app.messages.items = messages;
+        app.tool_calls = index;
+        app.messages.select(Some(0));
+        app.go_to_paired_message();
+        assert_eq!(app.messages.selected(), Some(1));
+        app.go_to_paired_message();
+        assert_eq!(app.messages.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_tool_renderers() {
+        use super::tools::{diff_lines, render_message_content, ToolCallIndex};
+
+        let diff: Vec<String> = diff_lines("a\nb\nc", "a\nB\nc\nd")
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(diff, vec!["  a", "- b", "+ B", "  c", "+ d"]);
+
+        let render = |name: &str, input: serde_json::Value, result: &str, is_error: bool| {
+            let messages = tool_exchange(name, input, result, is_error);
+            let index = ToolCallIndex::build(&messages);
+            render_message_content(&messages[0].message, &messages, &index, false)
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        let lines = render(
+            "Edit",
+            serde_json::json!({"file_path": "src/a.rs", "old_string": "x", "new_string": "y"}),
+            "Updated",
+            false,
+        );
+        assert_eq!(lines[1..4], ["  file: src/a.rs", "- x", "+ y"]);
+
+        let lines = render(
+            "Bash",
+            serde_json::json!({"command": "cargo test", "description": "Run tests"}),
+            "Exit code 101\ntest failed",
+            true,
+        );
+        assert_eq!(
+            lines[1..4],
+            [
+                "  # Run tests",
+                "  $ cargo test",
+                "◀ Output (exit status 101), 1.5s"
+            ]
+        );
+
+        let lines = render(
+            "Write",
+            serde_json::json!({"file_path": "notes.md", "content": "# Notes\nhello"}),
+            "Created",
+            false,
+        );
+        assert_eq!(
+            lines[1..4],
+            ["  file: notes.md (2 lines)", "# Notes", "hello"]
+        );
+
+        let lines = render(
+            "TodoWrite",
+            serde_json::json!({"todos": [
+                {"content": "Plan", "status": "completed"},
+                {"content": "Build", "status": "in_progress"},
+                {"content": "Ship", "status": "pending"},
+            ]}),
+            "Todos updated",
+            false,
+        );
+        assert_eq!(lines[1..4], ["  ☑ Plan", "  ◐ Build", "  ☐ Ship"]);
+    }
+
+    #[test]
+    fn test_tool_result_images() {
+        use super::export::save_images;
+        use super::project::Message;
+
+        // "hello" encoded as base64
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "type": "user",
+            "uuid": "abcdef123456",
+            "timestamp": "2025-01-15T10:00:00Z",
+            "message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "is_error": true, "content": [
+                    {"type": "text", "text": "Screenshot taken"},
+                    {"type": "image", "source": {
+                        "type": "base64", "media_type": "image/png", "data": "aGVsbG8="}},
+                ]},
+            ]},
+        }))
+        .unwrap();
+
+        let content = message.get_detailed_content();
+        assert!(content.contains("[Tool Result (error)]"));
+        assert!(content.contains("Screenshot taken"));
+        assert!(content.contains("[Image: image/png, 0.0 KB]"));
+
+        let images = message.images();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].decoded_size(), 5);
+        assert_eq!(images[0].decode().unwrap(), b"hello");
+        assert_eq!(images[0].extension(), "png");
+
+        let dir = std::env::temp_dir().join(format!("cocohibo-test-images-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (paths, renamed) = save_images(&images, &dir, "abcdef12").unwrap();
+        assert_eq!(paths, vec![dir.join("abcdef12-1.png")]);
+        assert_eq!(renamed, 0);
+        assert_eq!(std::fs::read(&paths[0]).unwrap(), b"hello");
+
+        // Saving again keeps the first file
+        let (paths, renamed) = save_images(&images, &dir, "abcdef12").unwrap();
+        assert_eq!(paths, vec![dir.join("abcdef12-1 (2).png")]);
+        assert_eq!(renamed, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        use super::markdown::render_line;
+        use ratatui::style::Modifier;
+
+        let has = |line: &ratatui::text::Line, text: &str, modifier: Modifier| {
+            line.spans
+                .iter()
+                .any(|span| span.content == text && span.style.add_modifier.contains(modifier))
+        };
+
+        let line = render_line("## Plan");
+        assert_eq!(line.to_string(), "Plan");
+        assert!(has(&line, "Plan", Modifier::BOLD));
+
+        let line = render_line("Use **bold**, *italic* and `code` in snake_case_names");
+        assert_eq!(
+            line.to_string(),
+            "Use bold, italic and code in snake_case_names"
+        );
+        assert!(has(&line, "bold", Modifier::BOLD));
+        assert!(has(&line, "italic", Modifier::ITALIC));
+
+        assert_eq!(render_line("  - item").to_string(), "  • item");
+        assert_eq!(render_line("- [x] done").to_string(), "☑ done");
+        assert_eq!(render_line("3. third").to_string(), "3. third");
+        assert_eq!(render_line("> quoted").to_string(), "│ quoted");
+        assert_eq!(
+            render_line("See [docs](https://example.com)").to_string(),
+            "See docs (https://example.com)"
+        );
+        // Unmatched delimiters are kept
+        assert_eq!(render_line("2 * 3 = 6").to_string(), "2 * 3 = 6");
+    }
+
     #[test]
     fn test_parse_messages_with_missing_fields() {
         let test_chat =
@@ -336,7 +1919,7 @@ mod tests {
 
             // Select first project and open it
             app.projects.select(Some(0));
-            if let Ok(_) = app.open_project() {
+            if app.open_project().is_ok() {
                 assert_eq!(app.screen, super::app::Screen::Chats);
                 assert!(!app.chats.is_empty());
 
@@ -364,16 +1947,19 @@ mod tests {
                 name: "test-project".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "another-project".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "debug-session".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
         ];
 
@@ -814,16 +2400,19 @@ mod tests {
                 name: "project1".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 1,
+                ..Default::default()
             },
             super::project::Project {
                 name: "project2".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 2,
+                ..Default::default()
             },
             super::project::Project {
                 name: "project3".to_string(),
                 last_modified: chrono::Utc::now(),
                 chat_count: 3,
+                ..Default::default()
             },
         ];
 
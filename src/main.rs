@@ -1,30 +1,144 @@
-use clap::Parser;
-use cocohibo::{app::App, events, ui};
+use clap::{Parser, Subcommand};
+use cocohibo::{
+    app::App,
+    commands, events,
+    export::{self, ExportFormat},
+    index::SearchIndex,
+    project::ChatTotalsCache,
+    stats::PriceTable,
+    ui,
+};
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
-use std::{env, io, path::PathBuf};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[derive(Parser)]
 #[command(name = "cocohibo")]
 #[command(about = "A browser for Claude Code history")]
 struct Cli {
-    #[arg(long, help = "Directory containing Claude Code projects")]
+    #[arg(
+        long,
+        global = true,
+        help = "Directory containing Claude Code projects"
+    )]
     projects_dir: Option<PathBuf>,
     #[arg(
         long,
         help = "Use vertical split (up/down) instead of horizontal split"
     )]
     vertical_split: bool,
+    #[arg(
+        long,
+        help = "JSON file with per-model token prices (USD per million tokens)"
+    )]
+    price_table: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Search chat files directly instead of using the cached search index"
+    )]
+    no_index: bool,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Seconds between rescans of the project and chat lists (0 disables)"
+    )]
+    refresh_interval: u64,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "List projects: name, last modified, chats, messages, tokens")]
+    ListProjects {
+        #[arg(long, help = "Print JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "List chats of a project: name, last modified, messages")]
+    ListChats {
+        #[arg(help = "Project name, as shown in the project list")]
+        project: String,
+        #[arg(long, help = "Print JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Print the messages of a chat")]
+    Show {
+        #[arg(help = "Project name, as shown in the project list")]
+        project: String,
+        #[arg(help = "Chat name, as shown in the chat list")]
+        chat: String,
+        #[arg(long, help = "Print JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Search messages: project, chat, message number, role, time, snippet")]
+    Search {
+        #[arg(help = "Words that must all appear in a message")]
+        query: String,
+        #[arg(long, help = "Only search the chats of this project")]
+        project: Option<String>,
+        #[arg(long, help = "Print JSON instead of plain text")]
+        json: bool,
+    },
+    #[command(about = "Export a chat to a file")]
+    Export {
+        #[arg(help = "Project name, as shown in the project list")]
+        project: String,
+        #[arg(help = "Chat name, as shown in the chat list")]
+        chat: String,
+        #[arg(long, value_enum, default_value = "markdown", help = "Export format")]
+        format: ExportFormat,
+        #[arg(
+            short,
+            long,
+            help = "Output file, or - for stdout [default: <CHAT>.<format extension>]"
+        )]
+        output: Option<PathBuf>,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     let projects_dir = get_projects_dir(cli.projects_dir);
 
+    if let Some(command) = cli.command {
+        let index_path = if cli.no_index {
+            None
+        } else {
+            SearchIndex::default_path(&projects_dir)
+        };
+        let totals_cache_path = ChatTotalsCache::default_path(&projects_dir);
+        return run_command(
+            command,
+            &projects_dir,
+            index_path.as_deref(),
+            totals_cache_path.as_deref(),
+        );
+    }
+
     let mut app = App::new(projects_dir, cli.vertical_split);
+    if cli.no_index {
+        app.index_path = None;
+    }
+    app.refresh_interval = Some(Duration::from_secs(cli.refresh_interval)).filter(|d| !d.is_zero());
+
+    if let Some(price_table_path) = get_price_table_path(cli.price_table) {
+        match PriceTable::load(&price_table_path) {
+            Ok(price_table) => app.price_table = price_table,
+            Err(e) => {
+                eprintln!("Error loading price table: {}", e);
+                return Err(e);
+            }
+        }
+    }
 
     if let Err(e) = app.load_projects() {
         eprintln!("Error loading projects: {}", e);
@@ -43,6 +157,81 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn run_command(
+    command: Command,
+    projects_dir: &Path,
+    index_path: Option<&Path>,
+    totals_cache_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::ListProjects { json } => {
+            print_output(commands::list_projects(
+                projects_dir,
+                totals_cache_path,
+                json,
+            )?)?;
+        }
+        Command::ListChats { project, json } => {
+            print_output(commands::list_chats(
+                projects_dir,
+                &project,
+                totals_cache_path,
+                json,
+            )?)?;
+        }
+        Command::Show {
+            project,
+            chat,
+            json,
+        } => {
+            print_output(commands::show_chat(projects_dir, &project, &chat, json)?)?;
+        }
+        Command::Search {
+            query,
+            project,
+            json,
+        } => {
+            print_output(commands::search(
+                projects_dir,
+                project.as_deref(),
+                &query,
+                index_path,
+                json,
+            )?)?;
+        }
+        Command::Export {
+            project,
+            chat,
+            format,
+            output,
+        } => {
+            let content = export::export_chat(projects_dir, &project, &chat, format)?;
+            // A path given with `-o` is written as is, the default one is never overwritten
+            let output = output.unwrap_or_else(|| {
+                export::unused_path(&export::default_export_path(&chat, format))
+            });
+            if output == Path::new("-") {
+                print_output(content)?;
+            } else {
+                fs::write(&output, content)?;
+                eprintln!("Exported chat to {}", output.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_output(mut output: String) -> io::Result<()> {
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    match io::stdout().lock().write_all(output.as_bytes()) {
+        // Output piped into e.g. `head` that exits early isn't an error
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
 fn get_projects_dir(cli_projects_dir: Option<PathBuf>) -> PathBuf {
     if let Some(projects_dir) = cli_projects_dir {
         return projects_dir;
@@ -59,6 +248,10 @@ fn get_projects_dir(cli_projects_dir: Option<PathBuf>) -> PathBuf {
     }
 }
 
+fn get_price_table_path(cli_price_table: Option<PathBuf>) -> Option<PathBuf> {
+    cli_price_table.or_else(|| env::var("COCOHIBO_PRICE_TABLE").ok().map(PathBuf::from))
+}
+
 fn setup_terminal() -> Result<(), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
     execute!(io::stdout(), EnterAlternateScreen)?;
@@ -81,6 +274,7 @@ fn run_app(app: &mut App) -> io::Result<()> {
         terminal.draw(|f| ui::render(f, app))?;
 
         events::handle_events(app, terminal_area)?;
+        app.tick();
 
         if app.should_quit {
             break;
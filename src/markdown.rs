@@ -0,0 +1,245 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+const RULE_WIDTH: usize = 40;
+
+fn heading_style(level: usize) -> Style {
+    let style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    if level == 1 {
+        style.add_modifier(Modifier::UNDERLINED)
+    } else {
+        style
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&marker| compact.chars().all(|c| c == marker))
+}
+
+/// Render one line of Markdown as styled spans. Fenced code blocks span
+/// several lines and are left to the caller.
+pub fn render_line(line: &str) -> Line<'static> {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+
+    // Headings
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&level) {
+        if let Some(text) = trimmed[level..].strip_prefix(' ') {
+            let text = text.trim_end_matches(['#', ' ']);
+            return Line::from(render_inline(text, heading_style(level)));
+        }
+    }
+
+    if is_rule(trimmed) {
+        return Line::styled("─".repeat(RULE_WIDTH), Style::default().fg(Color::DarkGray));
+    }
+
+    // Block quotes, possibly nested
+    if trimmed.starts_with('>') {
+        let mut text = trimmed;
+        let mut depth = 0;
+        while let Some(rest) = text.strip_prefix('>') {
+            text = rest.trim_start();
+            depth += 1;
+        }
+        let mut spans = vec![Span::styled(
+            format!("{}{}", indent, "│ ".repeat(depth)),
+            Style::default().fg(Color::DarkGray),
+        )];
+        spans.extend(render_inline(
+            text,
+            Style::default().add_modifier(Modifier::ITALIC),
+        ));
+        return Line::from(spans);
+    }
+
+    // Unordered lists, including task lists
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = trimmed.strip_prefix(marker) {
+            let (bullet, text) = if let Some(text) = text.strip_prefix("[ ] ") {
+                ("☐ ", text)
+            } else if let Some(text) = text
+                .strip_prefix("[x] ")
+                .or_else(|| text.strip_prefix("[X] "))
+            {
+                ("☑ ", text)
+            } else {
+                ("• ", text)
+            };
+            let mut spans = vec![Span::styled(
+                format!("{}{}", indent, bullet),
+                Style::default().fg(Color::Yellow),
+            )];
+            spans.extend(render_inline(text, Style::default()));
+            return Line::from(spans);
+        }
+    }
+
+    // Ordered lists
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &trimmed[digits..];
+        if rest.starts_with(". ") || rest.starts_with(") ") {
+            let mut spans = vec![Span::styled(
+                format!("{}{}", indent, &trimmed[..digits + 2]),
+                Style::default().fg(Color::Yellow),
+            )];
+            spans.extend(render_inline(&rest[2..], Style::default()));
+            return Line::from(spans);
+        }
+    }
+
+    Line::from(render_inline(line, Style::default()))
+}
+
+/// Render inline Markdown: code, emphasis, strikethrough and links.
+/// Delimiters without a closing counterpart are kept as text.
+pub fn render_inline(text: &str, base: Style) -> Vec<Span<'static>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut buffer = String::new();
+    let (mut bold, mut italic, mut strike) = (false, false, false);
+
+    let current_style = |bold: bool, italic: bool, strike: bool| {
+        let mut style = base;
+        if bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if strike {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        style
+    };
+    let run_length = |start: usize, c: char| chars[start..].iter().take_while(|&&x| x == c).count();
+    let find_run = |from: usize, c: char, n: usize| {
+        (from..chars.len().saturating_sub(n - 1))
+            .find(|&i| chars[i..i + n].iter().all(|&x| x == c) && run_length(i, c) == n)
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+
+        // Escaped punctuation is literal
+        if c == '\\'
+            && chars
+                .get(i + 1)
+                .is_some_and(|next| next.is_ascii_punctuation())
+        {
+            buffer.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+
+        if c == '`' {
+            let n = run_length(i, c);
+            if let Some(end) = find_run(i + n, c, n) {
+                flush(&mut spans, &mut buffer, current_style(bold, italic, strike));
+                let code: String = chars[i + n..end].iter().collect();
+                spans.push(Span::styled(
+                    code.trim().to_string(),
+                    base.fg(Color::Yellow),
+                ));
+                i = end + n;
+                continue;
+            }
+            buffer.extend(&chars[i..i + n]);
+            i += n;
+            continue;
+        }
+
+        // Links: [text](url)
+        if c == '[' {
+            let close = chars[i + 1..]
+                .iter()
+                .position(|&x| x == ']')
+                .map(|p| i + 1 + p);
+            if let Some(close) = close.filter(|&close| chars.get(close + 1) == Some(&'(')) {
+                if let Some(end) = chars[close + 2..].iter().position(|&x| x == ')') {
+                    let end = close + 2 + end;
+                    flush(&mut spans, &mut buffer, current_style(bold, italic, strike));
+                    let label: String = chars[i + 1..close].iter().collect();
+                    let url: String = chars[close + 2..end].iter().collect();
+                    let link_style = current_style(bold, italic, strike)
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::UNDERLINED);
+                    spans.extend(render_inline(&label, link_style));
+                    if url != label {
+                        spans.push(Span::styled(
+                            format!(" ({})", url),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+
+        if c == '*' || c == '_' || c == '~' {
+            let n = run_length(i, c).min(3);
+            let previous = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + n).copied();
+            // Underscores inside words (snake_case) aren't emphasis
+            let intraword = c == '_'
+                && previous.is_some_and(|p| p.is_alphanumeric())
+                && next.is_some_and(|n| n.is_alphanumeric());
+            let valid_run = if c == '~' { n == 2 } else { true };
+
+            if valid_run && !intraword {
+                let active = match (c, n) {
+                    ('~', _) => strike,
+                    (_, 1) => italic,
+                    (_, 2) => bold,
+                    _ => bold && italic,
+                };
+                let toggles = if active {
+                    // Closing: must follow non-whitespace
+                    previous.is_some_and(|p| !p.is_whitespace())
+                } else {
+                    // Opening: must precede non-whitespace and be closed later
+                    next.is_some_and(|n| !n.is_whitespace()) && find_run(i + n, c, n).is_some()
+                };
+                if toggles {
+                    flush(&mut spans, &mut buffer, current_style(bold, italic, strike));
+                    match (c, n) {
+                        ('~', _) => strike = !strike,
+                        (_, 1) => italic = !italic,
+                        (_, 2) => bold = !bold,
+                        _ => {
+                            bold = !bold;
+                            italic = !italic;
+                        }
+                    }
+                    i += n;
+                    continue;
+                }
+            }
+            buffer.extend(&chars[i..i + n]);
+            i += n;
+            continue;
+        }
+
+        buffer.push(c);
+        i += 1;
+    }
+
+    flush(&mut spans, &mut buffer, current_style(bold, italic, strike));
+    spans
+}
+
+fn flush(spans: &mut Vec<Span<'static>>, buffer: &mut String, style: Style) {
+    if !buffer.is_empty() {
+        spans.push(Span::styled(std::mem::take(buffer), style));
+    }
+}
@@ -0,0 +1,183 @@
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+use sublime_fuzzy::best_match;
+
+/// How a search query is matched against text.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MatchMode {
+    #[default]
+    Fuzzy,
+    Exact,
+    ExactCaseSensitive,
+    Regex,
+}
+
+impl MatchMode {
+    pub fn next(self) -> Self {
+        match self {
+            MatchMode::Fuzzy => MatchMode::Exact,
+            MatchMode::Exact => MatchMode::ExactCaseSensitive,
+            MatchMode::ExactCaseSensitive => MatchMode::Regex,
+            MatchMode::Regex => MatchMode::Fuzzy,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchMode::Fuzzy => "fuzzy",
+            MatchMode::Exact => "exact",
+            MatchMode::ExactCaseSensitive => "exact, case-sensitive",
+            MatchMode::Regex => "regex",
+        }
+    }
+}
+
+/// A query prepared for matching in one of the match modes.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Fuzzy(String),
+    Exact { query: String, case_sensitive: bool },
+    Regex(Regex),
+}
+
+impl Matcher {
+    /// Fails only for regular expressions that don't compile.
+    pub fn new(mode: MatchMode, query: &str) -> Result<Self, regex::Error> {
+        Ok(match mode {
+            MatchMode::Fuzzy => Matcher::Fuzzy(query.to_string()),
+            MatchMode::Exact => Matcher::Exact {
+                query: query.to_lowercase(),
+                case_sensitive: false,
+            },
+            MatchMode::ExactCaseSensitive => Matcher::Exact {
+                query: query.to_string(),
+                case_sensitive: true,
+            },
+            // Lowercase patterns match either case, like smartcase in editors
+            MatchMode::Regex => Matcher::Regex(
+                RegexBuilder::new(query)
+                    .case_insensitive(query.chars().all(|c| !c.is_uppercase()))
+                    .build()?,
+            ),
+        })
+    }
+
+    /// How well the text matches, higher is better, or `None` when it
+    /// doesn't. Only fuzzy matches are ranked, the other modes score 0.
+    pub fn score(&self, text: &str) -> Option<isize> {
+        match self {
+            Matcher::Fuzzy(query) => best_match(query, text).map(|m| m.score()),
+            Matcher::Exact {
+                query,
+                case_sensitive: true,
+            } => text.contains(query.as_str()).then_some(0),
+            Matcher::Exact { query, .. } => {
+                text.to_lowercase().contains(query.as_str()).then_some(0)
+            }
+            Matcher::Regex(regex) => regex.is_match(text).then_some(0),
+        }
+    }
+
+    /// Byte ranges of the text that make it match: the matched characters
+    /// of a fuzzy match, or every occurrence in the other modes.
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        let Matcher::Fuzzy(query) = self else {
+            return self.occurrences(text);
+        };
+        let Some(fuzzy_match) = best_match(query, text) else {
+            return Vec::new();
+        };
+
+        // The match has character indices
+        let char_ranges: Vec<Range<usize>> = text
+            .char_indices()
+            .map(|(start, c)| start..start + c.len_utf8())
+            .collect();
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for &index in fuzzy_match.matched_indices() {
+            let Some(range) = char_ranges.get(index).cloned() else {
+                continue;
+            };
+            match ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            }
+        }
+        ranges
+    }
+
+    /// Byte ranges of every occurrence of the query in the text. A fuzzy
+    /// match scatters over long text, so there the words of the query are
+    /// looked for instead, ignoring case.
+    pub fn occurrences(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Matcher::Fuzzy(query) => {
+                let mut ranges: Vec<Range<usize>> = query
+                    .split_whitespace()
+                    .flat_map(|word| find_ignoring_case(text, word))
+                    .collect();
+                ranges.sort_by_key(|range| range.start);
+                // Words may overlap each other
+                let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+                for range in ranges {
+                    match merged.last_mut() {
+                        Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                        _ => merged.push(range),
+                    }
+                }
+                merged
+            }
+            Matcher::Exact {
+                query,
+                case_sensitive: true,
+            } => find(text, query),
+            Matcher::Exact { query, .. } => find_ignoring_case(text, query),
+            Matcher::Regex(regex) => regex
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect(),
+        }
+    }
+}
+
+fn find(text: &str, needle: &str) -> Vec<Range<usize>> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    text.match_indices(needle)
+        .map(|(start, _)| start..start + needle.len())
+        .collect()
+}
+
+/// Occurrences of a needle, ignoring case. Lowercasing can change the byte
+/// length of characters either way, so the text is lowercased one character
+/// at a time, remembering where each lowercase byte came from.
+pub fn find_ignoring_case(text: &str, needle: &str) -> Vec<Range<usize>> {
+    let needle: String = needle.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lower = String::with_capacity(text.len());
+    let mut origins = Vec::with_capacity(text.len()); // Original character of each lowercase byte
+    for (start, c) in text.char_indices() {
+        for lower_c in c.to_lowercase() {
+            lower.push(lower_c);
+            origins.extend(std::iter::repeat_n(
+                start..start + c.len_utf8(),
+                lower_c.len_utf8(),
+            ));
+        }
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (start, _) in lower.match_indices(needle.as_str()) {
+        let range = origins[start].start..origins[start + needle.len() - 1].end;
+        // Matches within the lowercase form of one character map to the same range
+        if ranges.last().is_none_or(|last| range.start >= last.end) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
@@ -1,9 +1,14 @@
+use crate::stats::TokenTotals;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 fn default_timestamp() -> DateTime<Utc> {
     DateTime::<Utc>::from_timestamp(0, 0).unwrap_or_else(Utc::now)
@@ -15,11 +20,20 @@ fn default_uuid() -> String {
     format!("generated-uuid-{}", COUNTER.fetch_add(1, Ordering::SeqCst))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Project {
     pub name: String,
     pub last_modified: DateTime<Utc>,
     pub chat_count: usize,
+    // Aggregated over all chats of the project
+    #[serde(default)]
+    pub message_count: usize,
+    #[serde(default)]
+    pub tokens: TokenTotals,
+    #[serde(default)]
+    pub first_activity: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_activity: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +48,17 @@ pub struct MessageInner {
     #[serde(default)]
     pub role: String,
     pub content: Value,
+    // Assistant turns carry their API response metadata here
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    #[serde(default)]
+    pub stop_sequence: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +75,139 @@ pub struct Usage {
     pub service_tier: Option<String>,
 }
 
+/// A typed view of one block of message content.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentBlock {
+    Text(String),
+    Thinking(String),
+    ToolUse {
+        id: Option<String>,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: Option<String>,
+        content: String, // Text of the result, with placeholders for images
+        is_error: bool,
+    },
+    Image(ImageBlock),
+}
+
+/// An image attached to a message or returned by a tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageBlock {
+    pub media_type: String,
+    pub data: String, // Base64 encoded, empty for images given by URL
+}
+
+impl ImageBlock {
+    fn from_value(item: &Value) -> Self {
+        let source = item.get("source");
+        let source_field = |name: &str| {
+            source
+                .and_then(|source| source.get(name))
+                .and_then(|v| v.as_str())
+        };
+        Self {
+            media_type: source_field("media_type").unwrap_or("unknown").to_string(),
+            data: source_field("data").unwrap_or_default().to_string(),
+        }
+    }
+
+    /// Size of the decoded image in bytes.
+    pub fn decoded_size(&self) -> usize {
+        let padding = self.data.chars().rev().take_while(|&c| c == '=').count();
+        (self.data.len() * 3 / 4).saturating_sub(padding)
+    }
+
+    pub fn decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.decode(self.data.trim())
+    }
+
+    /// File extension matching the media type.
+    pub fn extension(&self) -> &str {
+        match self.media_type.strip_prefix("image/") {
+            Some("jpeg") => "jpg",
+            Some("svg+xml") => "svg",
+            Some(subtype) if !subtype.is_empty() => subtype,
+            _ => "bin",
+        }
+    }
+
+    pub fn placeholder(&self) -> String {
+        if self.data.is_empty() {
+            return format!("[Image: {}]", self.media_type);
+        }
+        let size = self.decoded_size();
+        let size = if size < 1024 * 1024 {
+            format!("{:.1} KB", size as f64 / 1024.0)
+        } else {
+            format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+        };
+        format!("[Image: {}, {}]", self.media_type, size)
+    }
+}
+
+/// Text of a `tool_result` block's content, which is either a string or a
+/// list of text and image blocks.
+fn tool_result_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item.get("type").and_then(|t| t.as_str()) {
+                Some("text") => item
+                    .get("text")
+                    .and_then(|t| t.as_str())
+                    .map(str::to_string),
+                Some("image") => Some(ImageBlock::from_value(item).placeholder()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Kinds of messages that can be hidden on the messages screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageCategory {
+    Meta,
+    Hook,
+    ToolCall,
+    ToolResult,
+    Thinking,
+    System,
+    Sidechain,
+}
+
+impl MessageCategory {
+    /// In the order of the keys toggling them, 1 to 7.
+    pub const ALL: [MessageCategory; 7] = [
+        MessageCategory::Meta,
+        MessageCategory::Hook,
+        MessageCategory::ToolCall,
+        MessageCategory::ToolResult,
+        MessageCategory::Thinking,
+        MessageCategory::System,
+        MessageCategory::Sidechain,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MessageCategory::Meta => "meta",
+            MessageCategory::Hook => "hooks",
+            MessageCategory::ToolCall => "tool calls",
+            MessageCategory::ToolResult => "tool results",
+            MessageCategory::Thinking => "thinking",
+            MessageCategory::System => "system",
+            MessageCategory::Sidechain => "sidechains",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     #[serde(rename = "type")]
@@ -62,7 +220,7 @@ pub struct Message {
     pub uuid: String,
     #[serde(rename = "parentUuid")]
     pub parent_uuid: Option<String>,
-    #[serde(default)]
+    #[serde(rename = "isSidechain", default)]
     pub is_sidechain: Option<bool>,
     #[serde(rename = "sessionId", default)]
     pub session_id: Option<String>,
@@ -153,17 +311,26 @@ impl Message {
                                         }
                                     }
                                     "tool_result" => {
-                                        if let Some(content) = item.get("content") {
-                                            if let Some(content_str) = content.as_str() {
-                                                if !result.is_empty() {
-                                                    result.push_str("\n\n");
-                                                }
-                                                result.push_str(&format!(
-                                                    "[Tool Result]\n{}",
-                                                    content_str
-                                                ));
-                                            }
+                                        if !result.is_empty() {
+                                            result.push_str("\n\n");
                                         }
+                                        let is_error = item
+                                            .get("is_error")
+                                            .and_then(|v| v.as_bool())
+                                            .unwrap_or(false);
+                                        result.push_str(if is_error {
+                                            "[Tool Result (error)]\n"
+                                        } else {
+                                            "[Tool Result]\n"
+                                        });
+                                        result.push_str(&tool_result_text(item.get("content")));
+                                    }
+                                    "image" => {
+                                        if !result.is_empty() {
+                                            result.push_str("\n\n");
+                                        }
+                                        result
+                                            .push_str(&ImageBlock::from_value(item).placeholder());
                                     }
                                     _ => {}
                                 }
@@ -244,21 +411,22 @@ impl Message {
                                         }
                                     }
                                     "tool_result" => {
-                                        if let Some(content) = item.get("content") {
-                                            if let Some(content_str) = content.as_str() {
-                                                if !result.is_empty() {
-                                                    result.push(' ');
-                                                }
-                                                let safe_content = content_str
-                                                    .chars()
-                                                    .take(200)
-                                                    .collect::<String>();
-                                                result.push_str(&format!(
-                                                    "[tool result: {}]",
-                                                    safe_content
-                                                ));
-                                            }
+                                        if !result.is_empty() {
+                                            result.push(' ');
                                         }
+                                        let safe_content = tool_result_text(item.get("content"))
+                                            .chars()
+                                            .take(200)
+                                            .collect::<String>();
+                                        result
+                                            .push_str(&format!("[tool result: {}]", safe_content));
+                                    }
+                                    "image" => {
+                                        if !result.is_empty() {
+                                            result.push(' ');
+                                        }
+                                        result
+                                            .push_str(&ImageBlock::from_value(item).placeholder());
                                     }
                                     _ => {}
                                 }
@@ -310,6 +478,153 @@ impl Message {
         .unwrap_or_else(|_| format!("[Error parsing {} content]", self.msg_type))
     }
 
+    pub fn get_model(&self) -> Option<&str> {
+        self.model
+            .as_deref()
+            .or_else(|| self.message.as_ref().and_then(|m| m.model.as_deref()))
+    }
+
+    pub fn get_usage(&self) -> Option<&Usage> {
+        self.usage
+            .as_ref()
+            .or_else(|| self.message.as_ref().and_then(|m| m.usage.as_ref()))
+    }
+
+    pub fn get_stop_reason(&self) -> Option<&str> {
+        self.stop_reason
+            .as_deref()
+            .or_else(|| self.message.as_ref().and_then(|m| m.stop_reason.as_deref()))
+    }
+
+    pub fn get_stop_sequence(&self) -> Option<&str> {
+        self.stop_sequence.as_deref().or_else(|| {
+            self.message
+                .as_ref()
+                .and_then(|m| m.stop_sequence.as_deref())
+        })
+    }
+
+    pub fn get_api_message_id(&self) -> Option<&str> {
+        self.message.as_ref().and_then(|m| m.id.as_deref())
+    }
+
+    /// All images of the message, including those inside tool results.
+    pub fn images(&self) -> Vec<ImageBlock> {
+        let content_value = if let Some(ref inner_message) = self.message {
+            &inner_message.content
+        } else if let Some(ref content) = self.content {
+            content
+        } else {
+            return Vec::new();
+        };
+        let Value::Array(items) = content_value else {
+            return Vec::new();
+        };
+
+        let mut images = Vec::new();
+        for item in items {
+            match item.get("type").and_then(|t| t.as_str()) {
+                Some("image") => images.push(ImageBlock::from_value(item)),
+                Some("tool_result") => {
+                    if let Some(Value::Array(results)) = item.get("content") {
+                        images.extend(
+                            results
+                                .iter()
+                                .filter(|r| r.get("type").and_then(|t| t.as_str()) == Some("image"))
+                                .map(ImageBlock::from_value),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        images
+    }
+
+    pub fn content_blocks(&self) -> Vec<ContentBlock> {
+        let content_value = if let Some(ref inner_message) = self.message {
+            &inner_message.content
+        } else if let Some(ref content) = self.content {
+            content
+        } else {
+            return Vec::new();
+        };
+
+        match content_value {
+            Value::String(s) => vec![ContentBlock::Text(s.clone())],
+            Value::Array(arr) => arr
+                .iter()
+                .filter_map(|item| {
+                    let str_field = |name: &str| item.get(name).and_then(|v| v.as_str());
+                    match str_field("type")? {
+                        "text" => Some(ContentBlock::Text(str_field("text")?.to_string())),
+                        "thinking" => {
+                            Some(ContentBlock::Thinking(str_field("thinking")?.to_string()))
+                        }
+                        "tool_use" => Some(ContentBlock::ToolUse {
+                            id: str_field("id").map(|s| s.to_string()),
+                            name: str_field("name").unwrap_or("unknown").to_string(),
+                            input: item.get("input").cloned().unwrap_or(Value::Null),
+                        }),
+                        "tool_result" => Some(ContentBlock::ToolResult {
+                            tool_use_id: str_field("tool_use_id").map(|s| s.to_string()),
+                            content: tool_result_text(item.get("content")),
+                            is_error: item
+                                .get("is_error")
+                                .and_then(|v| v.as_bool())
+                                .unwrap_or(false),
+                        }),
+                        "image" => Some(ContentBlock::Image(ImageBlock::from_value(item))),
+                        _ => None,
+                    }
+                })
+                .collect(),
+            Value::Null => Vec::new(),
+            other => vec![ContentBlock::Text(other.to_string())],
+        }
+    }
+
+    /// Whether the message is output of a hook. Hooks have no role of their
+    /// own, so they're recognized by their type or content.
+    pub fn is_hook(&self) -> bool {
+        let role = self.get_role();
+        if matches!(role, "user" | "assistant" | "system") {
+            return false;
+        }
+        let is_hook_text = |text: &str| text.contains("PostToolUse") || text.contains("Hook");
+        is_hook_text(role) || is_hook_text(&self.get_content_text())
+    }
+
+    /// Whether the message belongs to any of `categories`. Messages with
+    /// several content blocks belong to them only when all blocks do, so
+    /// hiding tool calls doesn't hide the text written before a call.
+    pub fn in_categories(&self, categories: &HashSet<MessageCategory>) -> bool {
+        let whole_message = [
+            (MessageCategory::Meta, self.is_meta == Some(true)),
+            (MessageCategory::Hook, self.is_hook()),
+            (MessageCategory::System, self.get_role() == "system"),
+            (MessageCategory::Sidechain, self.is_sidechain == Some(true)),
+        ];
+        if whole_message
+            .iter()
+            .any(|(category, applies)| *applies && categories.contains(category))
+        {
+            return true;
+        }
+
+        let blocks = self.content_blocks();
+        !blocks.is_empty()
+            && blocks.iter().all(|block| {
+                let category = match block {
+                    ContentBlock::ToolUse { .. } => MessageCategory::ToolCall,
+                    ContentBlock::ToolResult { .. } => MessageCategory::ToolResult,
+                    ContentBlock::Thinking(_) => MessageCategory::Thinking,
+                    ContentBlock::Text(_) | ContentBlock::Image(_) => return false,
+                };
+                categories.contains(&category)
+            })
+    }
+
     pub fn get_role(&self) -> &str {
         if let Some(ref inner_message) = self.message {
             if !inner_message.role.is_empty() {
@@ -335,6 +650,9 @@ pub struct HierarchicalMessage {
     pub is_initial: bool,
     pub chain_depth: usize,
     pub has_continuation: bool,
+    pub tree_prefix: String, // Guides and connector drawn before the message in the tree
+    pub descendant_count: usize, // Messages following from this one, listed right after it
+    pub branch: Option<(usize, usize)>, // Position and count among sibling branches
 }
 
 impl HierarchicalMessage {
@@ -344,10 +662,49 @@ impl HierarchicalMessage {
             is_initial,
             chain_depth,
             has_continuation: false,
+            tree_prefix: String::new(),
+            descendant_count: 0,
+            branch: None,
         }
     }
 }
 
+const TREE_BRANCH: &str = "├─";
+const TREE_LAST_BRANCH: &str = "└─";
+const TREE_GUIDE: &str = "│ ";
+const TREE_SPACE: &str = "  ";
+const TREE_SIDECHAIN: &str = "⤷ ";
+
+/// Whether a child starts a sidechain (a subagent run) rather than
+/// continuing the conversation of its parent.
+fn starts_sidechain(parent: &Message, child: &Message) -> bool {
+    child.is_sidechain == Some(true) && parent.is_sidechain != Some(true)
+}
+
+/// The `Task` call that spawned a sidechain without a parent: the one whose
+/// prompt is the sidechain's first message, or else the latest one before it.
+fn find_spawning_task<'a>(
+    sidechain_root: &Message,
+    task_calls: &'a [(String, DateTime<Utc>, String)],
+) -> Option<&'a str> {
+    let prompt = sidechain_root.get_detailed_content();
+    task_calls
+        .iter()
+        .find(|(_, _, task_prompt)| task_prompt.trim() == prompt.trim())
+        .or_else(|| {
+            task_calls
+                .iter()
+                .filter(|(_, timestamp, _)| *timestamp <= sidechain_root.timestamp)
+                .max_by_key(|(_, timestamp, _)| *timestamp)
+        })
+        .map(|(uuid, _, _)| uuid.as_str())
+}
+
+/// Arrange messages as a tree, each message followed by the messages that
+/// continue it. Chains of single replies stay in one column; where a message
+/// has several children (a retried or edited turn) each child starts a
+/// branch indented below it. Sidechains are listed right after the message
+/// they started from, or the `Task` call that spawned them.
 pub fn build_message_hierarchy(messages: Vec<Message>) -> Vec<HierarchicalMessage> {
     // Build a map of message UUID to message for quick lookup
     let mut message_map: HashMap<String, Message> = HashMap::new();
@@ -368,6 +725,47 @@ pub fn build_message_hierarchy(messages: Vec<Message>) -> Vec<HierarchicalMessag
         }
     }
 
+    // Attach sidechains without a parent to the Task call that spawned them
+    let mut task_calls: Vec<(String, DateTime<Utc>, String)> = message_map
+        .values()
+        .filter(|message| message.is_sidechain != Some(true))
+        .flat_map(|message| {
+            message
+                .content_blocks()
+                .into_iter()
+                .filter_map(move |block| match block {
+                    ContentBlock::ToolUse { name, input, .. } if name == "Task" => Some((
+                        message.uuid.clone(),
+                        message.timestamp,
+                        input
+                            .get("prompt")
+                            .and_then(|prompt| prompt.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                    )),
+                    _ => None,
+                })
+        })
+        .collect();
+    // The map's order is arbitrary, ties go to the same call every time
+    task_calls.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+    root_messages.retain(|uuid| {
+        let message = &message_map[uuid];
+        if message.is_sidechain != Some(true) {
+            return true;
+        }
+        match find_spawning_task(message, &task_calls) {
+            Some(task_uuid) => {
+                children_map
+                    .entry(task_uuid.to_string())
+                    .or_default()
+                    .push(uuid.clone());
+                false
+            }
+            None => true,
+        }
+    });
+
     // Sort root messages by timestamp
     root_messages.sort_by(|a, b| {
         let msg_a = message_map.get(a).unwrap();
@@ -383,12 +781,32 @@ pub fn build_message_hierarchy(messages: Vec<Message>) -> Vec<HierarchicalMessag
             // Add the root message as initial
             let mut root_hierarchical = HierarchicalMessage::new(root_message.clone(), true, 0);
             root_hierarchical.has_continuation = children_map.contains_key(&root_uuid);
+            let root_index = hierarchical_messages.len();
             hierarchical_messages.push(root_hierarchical);
 
+            // Sidechains of the root go between it and the chain below it
+            let chain_goes_on = children_map.get(&root_uuid).is_some_and(|children| {
+                children
+                    .iter()
+                    .filter_map(|uuid| message_map.get(uuid))
+                    .any(|child| !starts_sidechain(root_message, child))
+            });
+            let sidechain_guide = format!(
+                "{}{}",
+                TREE_SPACE,
+                if chain_goes_on {
+                    TREE_GUIDE
+                } else {
+                    TREE_SPACE
+                }
+            );
+
             // Add all messages in the chain stemming from this root
-            add_chain_messages(
+            hierarchical_messages[root_index].descendant_count = add_chain_messages(
                 &root_uuid,
                 1,
+                TREE_SPACE,
+                &sidechain_guide,
                 &message_map,
                 &children_map,
                 &mut hierarchical_messages,
@@ -399,40 +817,140 @@ pub fn build_message_hierarchy(messages: Vec<Message>) -> Vec<HierarchicalMessag
     hierarchical_messages
 }
 
+/// Add the descendants of a message, with `guide` drawn before their
+/// connectors and `sidechain_guide` before the sidechains started from it.
+/// Returns the number of messages added.
 fn add_chain_messages(
     parent_uuid: &str,
     chain_depth: usize,
+    guide: &str,
+    sidechain_guide: &str,
     message_map: &HashMap<String, Message>,
     children_map: &HashMap<String, Vec<String>>,
     result: &mut Vec<HierarchicalMessage>,
-) {
-    if let Some(child_uuids) = children_map.get(parent_uuid) {
-        // Sort children by timestamp
-        let mut sorted_children: Vec<_> = child_uuids
-            .iter()
-            .filter_map(|uuid| message_map.get(uuid).map(|msg| (uuid.clone(), msg.clone())))
-            .collect();
-        sorted_children.sort_by(|(_, a), (_, b)| a.timestamp.cmp(&b.timestamp));
-
-        for (child_uuid, child_message) in sorted_children {
-            let mut child_hierarchical =
-                HierarchicalMessage::new(child_message, false, chain_depth);
-            child_hierarchical.has_continuation = children_map.contains_key(&child_uuid);
-            result.push(child_hierarchical);
-
-            // Continue the chain recursively
-            add_chain_messages(
-                &child_uuid,
-                chain_depth + 1,
-                message_map,
-                children_map,
-                result,
-            );
+) -> usize {
+    let (Some(parent), Some(child_uuids)) =
+        (message_map.get(parent_uuid), children_map.get(parent_uuid))
+    else {
+        return 0;
+    };
+
+    // Sort children by timestamp
+    let mut sorted_children: Vec<_> = child_uuids
+        .iter()
+        .filter_map(|uuid| message_map.get(uuid).map(|msg| (uuid.clone(), msg.clone())))
+        .collect();
+    sorted_children.sort_by_key(|(_, msg)| msg.timestamp);
+    let (sidechains, sorted_children): (Vec<_>, Vec<_>) = sorted_children
+        .into_iter()
+        .partition(|(_, child)| starts_sidechain(parent, child));
+
+    // Children continuing a message's conversation, leaving out sidechains
+    let chain_child_count = |uuid: &str| {
+        let Some(message) = message_map.get(uuid) else {
+            return 0;
+        };
+        children_map.get(uuid).map_or(0, |children| {
+            children
+                .iter()
+                .filter_map(|child| message_map.get(child))
+                .filter(|child| !starts_sidechain(message, child))
+                .count()
+        })
+    };
+    let is_fork = sorted_children.len() > 1;
+
+    let mut added = 0;
+    // Each sidechain is drawn as a chain of its own, hanging off its first message
+    for (child_uuid, child_message) in &sidechains {
+        let mut child_hierarchical =
+            HierarchicalMessage::new(child_message.clone(), false, chain_depth);
+        child_hierarchical.has_continuation = children_map.contains_key(child_uuid);
+        child_hierarchical.tree_prefix = format!("{}{}", sidechain_guide, TREE_SIDECHAIN);
+        let child_index = result.len();
+        result.push(child_hierarchical);
+
+        let descendants = add_chain_messages(
+            child_uuid,
+            chain_depth + 1,
+            sidechain_guide,
+            sidechain_guide,
+            message_map,
+            children_map,
+            result,
+        );
+        result[child_index].descendant_count = descendants;
+        added += 1 + descendants;
+    }
+
+    for (position, (child_uuid, child_message)) in sorted_children.iter().enumerate() {
+        let (connector, child_guide) = if is_fork {
+            // Each child starts a branch, the children of which are indented below it
+            if position + 1 == sorted_children.len() {
+                (TREE_LAST_BRANCH, format!("{}{}", guide, TREE_SPACE))
+            } else {
+                (TREE_BRANCH, format!("{}{}", guide, TREE_GUIDE))
+            }
+        } else if chain_child_count(child_uuid) == 1 {
+            // The chain goes on in the same column
+            (TREE_BRANCH, guide.to_string())
+        } else {
+            // The chain ends here, possibly forking into branches
+            (TREE_LAST_BRANCH, format!("{}{}", guide, TREE_SPACE))
+        };
+        // Sidechains hang below the connector, continuing its line if it has one
+        let child_sidechain_guide = format!(
+            "{}{}",
+            guide,
+            if connector == TREE_BRANCH {
+                TREE_GUIDE
+            } else {
+                TREE_SPACE
+            }
+        );
+
+        let mut child_hierarchical =
+            HierarchicalMessage::new(child_message.clone(), false, chain_depth);
+        child_hierarchical.has_continuation = children_map.contains_key(child_uuid);
+        child_hierarchical.tree_prefix = format!("{}{}", guide, connector);
+        if is_fork {
+            child_hierarchical.branch = Some((position + 1, sorted_children.len()));
         }
+        let child_index = result.len();
+        result.push(child_hierarchical);
+
+        // Continue the chain recursively
+        let descendants = add_chain_messages(
+            child_uuid,
+            chain_depth + 1,
+            &child_guide,
+            &child_sidechain_guide,
+            message_map,
+            children_map,
+            result,
+        );
+        result[child_index].descendant_count = descendants;
+        added += 1 + descendants;
     }
+    added
 }
 
-pub fn discover_projects(projects_dir: &Path) -> Result<Vec<Project>, Box<dyn std::error::Error>> {
+pub fn discover_projects(
+    projects_dir: &Path,
+    totals_cache: &mut ChatTotalsCache,
+) -> Result<Vec<Project>, Box<dyn std::error::Error>> {
+    let mut projects = Vec::new();
+
+    for path in project_dirs(projects_dir)? {
+        projects.push(load_project(&path, totals_cache)?);
+    }
+
+    projects.sort_by_key(|p| std::cmp::Reverse(p.last_modified));
+    Ok(projects)
+}
+
+/// Paths of all project directories in the projects directory.
+pub fn project_dirs(projects_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
     if !projects_dir.exists() {
         return Err(format!(
             "Projects directory does not exist: {}",
@@ -441,38 +959,63 @@ pub fn discover_projects(projects_dir: &Path) -> Result<Vec<Project>, Box<dyn st
         .into());
     }
 
-    let mut projects = Vec::new();
-
+    let mut dirs = Vec::new();
     for entry in fs::read_dir(projects_dir)? {
         let entry = entry?;
         let path = entry.path();
-
         if path.is_dir() {
-            let name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            let metadata = fs::metadata(&path)?;
-            let modified = metadata.modified()?;
-            let last_modified = DateTime::<Utc>::from(modified);
-
-            let chat_count = count_chats(&path)?;
-
-            projects.push(Project {
-                name,
-                last_modified,
-                chat_count,
-            });
+            dirs.push(path);
+        }
+    }
+    Ok(dirs)
+}
+
+/// Load one project with totals aggregated over its chats. Only chats that
+/// changed since their totals were cached are parsed.
+pub fn load_project(
+    project_dir: &Path,
+    totals_cache: &mut ChatTotalsCache,
+) -> Result<Project, Box<dyn std::error::Error>> {
+    let name = project_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let metadata = fs::metadata(project_dir)?;
+    let modified = metadata.modified()?;
+    let last_modified = DateTime::<Utc>::from(modified);
+
+    let mut project = Project {
+        name,
+        last_modified,
+        ..Default::default()
+    };
+    aggregate_project_chats(&mut project, project_dir, totals_cache)?;
+    Ok(project)
+}
+
+/// Cheap fingerprint of a project directory's chat files (names, sizes and
+/// modification times), used to tell whether it needs to be reloaded.
+pub fn project_dir_signature(project_dir: &Path) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(project_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "jsonl") {
+            let metadata = entry.metadata()?;
+            entries.push((entry.file_name(), metadata.len(), metadata.modified()?));
         }
     }
+    entries.sort();
 
-    projects.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
-    Ok(projects)
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    Ok(hasher.finish())
 }
 
-pub fn discover_chats(project_dir: &Path) -> Result<Vec<Chat>, Box<dyn std::error::Error>> {
+/// Chat files of a project, newest first.
+pub fn chat_files(project_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
     if !project_dir.exists() {
         return Err(format!(
             "Project directory does not exist: {}",
@@ -481,77 +1024,234 @@ pub fn discover_chats(project_dir: &Path) -> Result<Vec<Chat>, Box<dyn std::erro
         .into());
     }
 
-    let mut chats = Vec::new();
-
+    let mut files = Vec::new();
     for entry in fs::read_dir(project_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
+        let path = entry?.path();
         if path.is_file() && path.extension().is_some_and(|ext| ext == "jsonl") {
-            let name = path
-                .file_stem()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            let metadata = fs::metadata(&path)?;
-            let modified = metadata.modified()?;
-            let last_modified = DateTime::<Utc>::from(modified);
-
-            let message_count = count_messages(&path)?;
-
-            chats.push(Chat {
-                name,
-                last_modified,
-                message_count,
-            });
+            let modified = fs::metadata(&path)?.modified()?;
+            files.push((path, modified));
         }
     }
 
-    chats.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Chats of a project, newest first. Message counts come from the totals
+/// cache, so only chats that changed since they were counted are read.
+pub fn discover_chats(
+    project_dir: &Path,
+    totals_cache: &mut ChatTotalsCache,
+) -> Result<Vec<Chat>, Box<dyn std::error::Error>> {
+    let mut chats = Vec::new();
+    for path in chat_files(project_dir)? {
+        let name = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let last_modified = DateTime::<Utc>::from(fs::metadata(&path)?.modified()?);
+        // A chat that can't be parsed is listed without messages
+        let message_count = totals_cache
+            .chat_totals(&path)?
+            .map_or(0, |totals| totals.message_count);
+
+        chats.push(Chat {
+            name,
+            last_modified,
+            message_count,
+        });
+    }
     Ok(chats)
 }
 
-fn count_chats(project_dir: &Path) -> Result<usize, Box<dyn std::error::Error>> {
-    let mut count = 0;
+/// Message count, token usage and activity range of one chat file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatTotals {
+    pub message_count: usize,
+    pub tokens: TokenTotals,
+    pub first_activity: Option<DateTime<Utc>>,
+    pub last_activity: Option<DateTime<Utc>>,
+}
 
+impl ChatTotals {
+    fn from_messages(messages: &[Message]) -> Self {
+        let mut totals = ChatTotals {
+            message_count: messages.len(),
+            tokens: crate::stats::total_usage(messages.iter()),
+            ..Default::default()
+        };
+        // Messages without a timestamp get the epoch, which isn't real activity
+        for timestamp in messages
+            .iter()
+            .map(|m| m.timestamp)
+            .filter(|t| t.timestamp() != 0)
+        {
+            totals.add_activity(timestamp, timestamp);
+        }
+        totals
+    }
+
+    fn add_activity(&mut self, first: DateTime<Utc>, last: DateTime<Utc>) {
+        if self.first_activity.is_none_or(|current| first < current) {
+            self.first_activity = Some(first);
+        }
+        if self.last_activity.is_none_or(|current| last > current) {
+            self.last_activity = Some(last);
+        }
+    }
+}
+
+const TOTALS_CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedChatTotals {
+    size: u64,
+    mtime_nanos: u64,
+    totals: Option<ChatTotals>, // None for chats that can't be parsed
+}
+
+/// Totals of chat files by path. A file is parsed again only when its size
+/// or modification time changes, so projects load quickly even while a
+/// session keeps appending to one of their chats.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChatTotalsCache {
+    version: u32,
+    files: HashMap<String, CachedChatTotals>,
+    #[serde(skip)]
+    changed: bool, // Since it was loaded or saved
+}
+
+impl ChatTotalsCache {
+    /// Cache location in the user's cache directory, one file per projects directory.
+    pub fn default_path(projects_dir: &Path) -> Option<PathBuf> {
+        crate::index::cache_path(projects_dir, "totals").map(|path| path.with_extension("json"))
+    }
+
+    /// Load a saved cache. A missing, unreadable or outdated one yields an
+    /// empty cache.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ChatTotalsCache>(&content).ok())
+            .filter(|cache| cache.version == TOTALS_CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Save the cache if anything changed, leaving out chats that no longer exist.
+    pub fn save(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.changed {
+            return Ok(());
+        }
+        self.files
+            .retain(|chat_file, _| Path::new(chat_file).exists());
+        self.version = TOTALS_CACHE_VERSION;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so a crash never leaves a truncated cache
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Whether totals were computed since the cache was loaded or saved.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Totals of a chat file, or `None` when it can't be parsed.
+    pub fn chat_totals(
+        &mut self,
+        chat_file: &Path,
+    ) -> Result<Option<ChatTotals>, Box<dyn std::error::Error>> {
+        let metadata = fs::metadata(chat_file)?;
+        let size = metadata.len();
+        let mtime_nanos = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        let key = chat_file.to_string_lossy().to_string();
+        if let Some(cached) = self.files.get(&key) {
+            if cached.size == size && cached.mtime_nanos == mtime_nanos {
+                return Ok(cached.totals.clone());
+            }
+        }
+
+        let totals = load_messages(chat_file)
+            .ok()
+            .map(|messages| ChatTotals::from_messages(&messages));
+        self.files.insert(
+            key,
+            CachedChatTotals {
+                size,
+                mtime_nanos,
+                totals: totals.clone(),
+            },
+        );
+        self.changed = true;
+        Ok(totals)
+    }
+}
+
+fn aggregate_project_chats(
+    project: &mut Project,
+    project_dir: &Path,
+    totals_cache: &mut ChatTotalsCache,
+) -> Result<(), Box<dyn std::error::Error>> {
     for entry in fs::read_dir(project_dir)? {
         let entry = entry?;
         let path = entry.path();
 
         if path.is_file() && path.extension().is_some_and(|ext| ext == "jsonl") {
-            count += 1;
+            project.chat_count += 1;
+
+            // A chat that can't be parsed still counts, it just adds no totals
+            let Some(totals) = totals_cache.chat_totals(&path)? else {
+                continue;
+            };
+
+            project.message_count += totals.message_count;
+            project.tokens.add(&totals.tokens);
+            if let (Some(first), Some(last)) = (totals.first_activity, totals.last_activity) {
+                if project.first_activity.is_none_or(|current| first < current) {
+                    project.first_activity = Some(first);
+                }
+                if project.last_activity.is_none_or(|current| last > current) {
+                    project.last_activity = Some(last);
+                }
+            }
         }
     }
 
-    Ok(count)
+    Ok(())
+}
+
+pub fn chat_file_path(projects_dir: &Path, project_name: &str, chat_name: &str) -> PathBuf {
+    projects_dir
+        .join(project_name)
+        .join(format!("{}.jsonl", chat_name))
 }
 
 pub fn load_messages(chat_file: &Path) -> Result<Vec<Message>, Box<dyn std::error::Error>> {
+    load_messages_with_offset(chat_file).map(|(messages, _)| messages)
+}
+
+/// Load all messages of a chat file, also returning the offset (in bytes) up
+/// to which the file was consumed, for use with `load_appended_messages`.
+pub fn load_messages_with_offset(
+    chat_file: &Path,
+) -> Result<(Vec<Message>, u64), Box<dyn std::error::Error>> {
     if !chat_file.exists() {
         return Err(format!("Chat file does not exist: {}", chat_file.display()).into());
     }
 
     let content = fs::read_to_string(chat_file)?;
-    let mut messages = Vec::new();
-    let mut error_count = 0;
-
-    for (line_num, line) in content.lines().enumerate() {
-        if !line.trim().is_empty() {
-            match serde_json::from_str::<Message>(line) {
-                Ok(message) => messages.push(message),
-                Err(e) => {
-                    error_count += 1;
-                    eprintln!(
-                        "Warning: Failed to parse message at line {}: {}",
-                        line_num + 1,
-                        e
-                    );
-                    // Continue processing other messages instead of failing completely
-                }
-            }
-        }
-    }
+    let (messages, consumed, error_count) = parse_message_lines(&content);
 
     if messages.is_empty() && error_count > 0 {
         return Err(format!(
@@ -562,10 +1262,69 @@ pub fn load_messages(chat_file: &Path) -> Result<Vec<Message>, Box<dyn std::erro
         .into());
     }
 
-    Ok(messages)
+    Ok((messages, consumed as u64))
 }
 
-fn count_messages(chat_file: &Path) -> Result<usize, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(chat_file)?;
-    Ok(content.lines().count())
+/// Parse messages appended to a chat file after `offset` (in bytes). Returns
+/// the new messages and the new offset, which is at the end of the last
+/// complete line.
+pub fn load_appended_messages(
+    chat_file: &Path,
+    offset: u64,
+) -> Result<(Vec<Message>, u64), Box<dyn std::error::Error>> {
+    let mut file = fs::File::open(chat_file)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+
+    // Only whole lines, the file may end in the middle of a line or character
+    let complete = bytes
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |end| end + 1);
+    let content = String::from_utf8_lossy(&bytes[..complete]);
+    let (messages, _, _) = parse_message_lines(&content);
+    Ok((messages, offset + complete as u64))
+}
+
+/// Parse JSONL content, returning the messages, the number of bytes consumed
+/// and the number of lines that failed to parse.
+///
+/// An unterminated last line that doesn't parse is most likely still being
+/// written, so it's left unconsumed (and not warned about) to be read again
+/// once it's complete.
+fn parse_message_lines(content: &str) -> (Vec<Message>, usize, usize) {
+    let mut messages = Vec::new();
+    let mut consumed = 0;
+    let mut error_count = 0;
+
+    for (line_num, line) in content.split_inclusive('\n').enumerate() {
+        let is_complete = line.ends_with('\n');
+        if line.trim().is_empty() {
+            consumed += line.len();
+            continue;
+        }
+
+        match serde_json::from_str::<Message>(line) {
+            Ok(message) => {
+                messages.push(message);
+                consumed += line.len();
+            }
+            Err(_) if !is_complete => {
+                error_count += 1;
+            }
+            Err(e) => {
+                error_count += 1;
+                consumed += line.len();
+                eprintln!(
+                    "Warning: Failed to parse message at line {}: {}",
+                    line_num + 1,
+                    e
+                );
+                // Continue processing other messages instead of failing completely
+            }
+        }
+    }
+
+    (messages, consumed, error_count)
 }
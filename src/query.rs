@@ -0,0 +1,236 @@
+use crate::project::{ContentBlock, HierarchicalMessage, Message};
+use crate::stats::TokenTotals;
+use crate::tools::ToolCallIndex;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Something a message can contain, for `has:` qualifiers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+    Text,
+    Thinking,
+    ToolCall,
+    ToolResult,
+    Error,
+    Image,
+}
+
+impl Feature {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Feature::Text),
+            "thinking" => Some(Feature::Thinking),
+            "tool" | "call" => Some(Feature::ToolCall),
+            "result" => Some(Feature::ToolResult),
+            "error" => Some(Feature::Error),
+            "image" => Some(Feature::Image),
+            _ => None,
+        }
+    }
+
+    fn in_block(self, block: &ContentBlock) -> bool {
+        match (self, block) {
+            (Feature::Text, ContentBlock::Text(text)) => !text.trim().is_empty(),
+            (Feature::Thinking, ContentBlock::Thinking(_)) => true,
+            (Feature::ToolCall, ContentBlock::ToolUse { .. }) => true,
+            (Feature::ToolResult, ContentBlock::ToolResult { .. }) => true,
+            (Feature::Error, ContentBlock::ToolResult { is_error, .. }) => *is_error,
+            (Feature::Image, ContentBlock::Image(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, left: u64, right: u64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+/// A condition on one field of a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Role(String),
+    Tool(String),
+    Model(String),
+    Branch(String),
+    After(DateTime<Utc>),
+    Before(DateTime<Utc>),
+    Has(Feature),
+    Tokens(Comparison, u64),
+    Text(String), // Negated free text, the rest is matched by the caller
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub filter: Filter,
+    pub negated: bool,
+}
+
+/// A message search: field qualifiers such as `role:user`, `tool:Bash`,
+/// `after:2025-01-01` or `tokens>10000`, each negated with a leading `-`,
+/// and free text. Words that aren't valid qualifiers are free text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageQuery {
+    pub terms: Vec<Term>,
+    pub text: String,
+}
+
+/// A point in time from a date (`2025-01-15`), a time ago (`3h`, `7d`,
+/// `2w`), `today` or `yesterday`.
+fn parse_time(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start_of_today = now.date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+    match value {
+        "today" => return Some(start_of_today),
+        "yesterday" => return Some(start_of_today - Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+
+    let unit = value.chars().last()?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    // Out of range amounts are no time at all
+    let ago = match unit {
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => None,
+    }?;
+    now.checked_sub_signed(ago)
+}
+
+/// A token count, optionally with a `k` or `m` suffix.
+fn parse_count(value: &str) -> Option<u64> {
+    let lower = value.to_lowercase();
+    let (number, multiplier) = if let Some(number) = lower.strip_suffix('k') {
+        (number, 1_000.0)
+    } else if let Some(number) = lower.strip_suffix('m') {
+        (number, 1_000_000.0)
+    } else {
+        (lower.as_str(), 1.0)
+    };
+    let count: f64 = number.parse().ok()?;
+    (count >= 0.0).then_some((count * multiplier) as u64)
+}
+
+fn parse_filter(word: &str, now: DateTime<Utc>) -> Option<Filter> {
+    if let Some(rest) = word.strip_prefix("tokens") {
+        let (comparison, value) = if let Some(value) = rest.strip_prefix(">=") {
+            (Comparison::GreaterOrEqual, value)
+        } else if let Some(value) = rest.strip_prefix("<=") {
+            (Comparison::LessOrEqual, value)
+        } else if let Some(value) = rest.strip_prefix('>') {
+            (Comparison::Greater, value)
+        } else if let Some(value) = rest.strip_prefix('<') {
+            (Comparison::Less, value)
+        } else {
+            return None;
+        };
+        return Some(Filter::Tokens(comparison, parse_count(value)?));
+    }
+
+    let (key, value) = word.split_once(':')?;
+    if value.is_empty() {
+        return None;
+    }
+    let value_lower = value.to_lowercase();
+    match key.to_lowercase().as_str() {
+        "role" => Some(Filter::Role(value_lower)),
+        "tool" => Some(Filter::Tool(value_lower)),
+        "model" => Some(Filter::Model(value_lower)),
+        "branch" => Some(Filter::Branch(value.to_string())),
+        "after" => parse_time(&value_lower, now).map(Filter::After),
+        "before" => parse_time(&value_lower, now).map(Filter::Before),
+        "has" => Feature::parse(&value_lower).map(Filter::Has),
+        _ => None,
+    }
+}
+
+impl MessageQuery {
+    /// Parse a query, with relative times counted back from `now`.
+    pub fn parse(query: &str, now: DateTime<Utc>) -> Self {
+        let mut terms = Vec::new();
+        let mut words = Vec::new();
+        for word in query.split_whitespace() {
+            let (negated, unsigned) = match word.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, word),
+            };
+            match parse_filter(unsigned, now) {
+                Some(filter) => terms.push(Term { filter, negated }),
+                None if negated => terms.push(Term {
+                    filter: Filter::Text(unsigned.to_lowercase()),
+                    negated,
+                }),
+                None => words.push(word),
+            }
+        }
+        Self {
+            terms,
+            text: words.join(" "),
+        }
+    }
+
+    /// Whether the message satisfies all qualifiers. The free text is left
+    /// to the caller, which may match it fuzzily.
+    pub fn matches(
+        &self,
+        message: &Message,
+        messages: &[HierarchicalMessage],
+        tool_calls: &ToolCallIndex,
+    ) -> bool {
+        if self.terms.is_empty() {
+            return true;
+        }
+
+        // Computed only for the qualifiers that need them
+        let mut blocks = None;
+        let mut tool_names = None;
+        let mut content_text = None;
+
+        self.terms.iter().all(|term| {
+            let satisfied = match &term.filter {
+                Filter::Role(role) => message.get_role().eq_ignore_ascii_case(role),
+                Filter::Tool(tool) => tool_names
+                    .get_or_insert_with(|| tool_calls.tool_names(messages, message))
+                    .iter()
+                    .any(|name: &String| name.to_lowercase() == *tool),
+                Filter::Model(model) => message
+                    .get_model()
+                    .is_some_and(|name| name.to_lowercase().contains(model.as_str())),
+                Filter::Branch(branch) => message.git_branch.as_deref() == Some(branch.as_str()),
+                Filter::After(time) => message.timestamp >= *time,
+                Filter::Before(time) => message.timestamp < *time,
+                Filter::Has(feature) => blocks
+                    .get_or_insert_with(|| message.content_blocks())
+                    .iter()
+                    .any(|block| feature.in_block(block)),
+                Filter::Tokens(comparison, count) => {
+                    let mut tokens = TokenTotals::default();
+                    if let Some(usage) = message.get_usage() {
+                        tokens.add_usage(usage);
+                    }
+                    comparison.holds(tokens.total(), *count)
+                }
+                Filter::Text(text) => content_text
+                    .get_or_insert_with(|| message.get_content_text().to_lowercase())
+                    .contains(text.as_str()),
+            };
+            satisfied != term.negated
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use crate::project::{build_message_hierarchy, chat_files, load_messages};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const SNIPPET_WIDTH: usize = 120;
+const MAX_TOKEN_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub project: String,
+    pub chat: String,
+    /// Position of the message in the chat's message hierarchy (0-based)
+    pub message_index: usize,
+    pub timestamp: DateTime<Utc>,
+    pub role: String,
+    pub snippet: String,
+}
+
+/// Split text into lowercase alphanumeric tokens.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| !token.is_empty() && token.chars().count() <= MAX_TOKEN_LEN)
+        .map(|token| token.to_lowercase())
+}
+
+/// Split a query into terms, tokenized like the searched text. A message
+/// matches when every term starts one of its tokens, so `migr` finds
+/// `migration` and `foo.bar` finds text with both `foo` and `bar`.
+pub fn query_terms(query: &str) -> Vec<String> {
+    tokenize(query).collect()
+}
+
+pub fn matches_terms(tokens: &HashSet<String>, terms: &[String]) -> bool {
+    !terms.is_empty()
+        && terms
+            .iter()
+            .all(|term| tokens.iter().any(|token| token.starts_with(term.as_str())))
+}
+
+/// Single line excerpt of `text` around the first occurrence of `term`.
+pub fn make_snippet(text: &str, term: &str) -> String {
+    let flat: String = text
+        .chars()
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+    let chars: Vec<char> = flat.chars().collect();
+    let lower: Vec<char> = flat.to_lowercase().chars().collect();
+    let term: Vec<char> = term.chars().collect();
+
+    // Lowercasing can change the length of some strings, only trust the
+    // match position when it didn't
+    let match_pos = if lower.len() == chars.len() && !term.is_empty() {
+        lower.windows(term.len()).position(|w| w == term.as_slice())
+    } else {
+        None
+    };
+
+    let start = match_pos
+        .map(|pos| pos.saturating_sub(SNIPPET_WIDTH / 3))
+        .unwrap_or(0);
+    let end = (start + SNIPPET_WIDTH).min(chars.len());
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    snippet.extend(chars[start..end].iter());
+    if end < chars.len() {
+        snippet.push_str("...");
+    }
+    snippet.trim().to_string()
+}
+
+/// Search the messages of one project, or of all projects when `project` is
+/// `None`, by scanning every chat file.
+pub fn search_messages(
+    projects_dir: &Path,
+    project: Option<&str>,
+    query: &str,
+) -> Result<Vec<SearchHit>, Box<dyn std::error::Error>> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let project_names = match project {
+        Some(name) => vec![name.to_string()],
+        None => {
+            let mut names = Vec::new();
+            for entry in fs::read_dir(projects_dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    names.push(
+                        path.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string(),
+                    );
+                }
+            }
+            names.sort();
+            names
+        }
+    };
+
+    let mut hits = Vec::new();
+    for project_name in project_names {
+        let project_dir = projects_dir.join(&project_name);
+        for chat_path in chat_files(&project_dir)? {
+            let chat = chat_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            // Skip chats that can't be parsed instead of failing the whole search
+            let Ok(messages) = load_messages(&chat_path) else {
+                continue;
+            };
+
+            for (message_index, hierarchical) in
+                build_message_hierarchy(messages).iter().enumerate()
+            {
+                let message = &hierarchical.message;
+                let text = message.get_detailed_content();
+                if matches_terms(&tokenize(&text).collect(), &terms) {
+                    hits.push(SearchHit {
+                        project: project_name.clone(),
+                        chat: chat.clone(),
+                        message_index,
+                        timestamp: message.timestamp,
+                        role: message.get_role().to_string(),
+                        snippet: make_snippet(&text, &terms[0]),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(hits)
+}
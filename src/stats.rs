@@ -0,0 +1,225 @@
+use crate::project::{Message, Usage};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenTotals {
+    pub input: u64,
+    pub output: u64,
+    pub cache_creation: u64,
+    pub cache_read: u64,
+}
+
+impl TokenTotals {
+    pub fn add_usage(&mut self, usage: &Usage) {
+        self.input += usage.input_tokens.unwrap_or(0) as u64;
+        self.output += usage.output_tokens.unwrap_or(0) as u64;
+        self.cache_creation += usage.cache_creation_input_tokens.unwrap_or(0) as u64;
+        self.cache_read += usage.cache_read_input_tokens.unwrap_or(0) as u64;
+    }
+
+    pub fn add(&mut self, other: &TokenTotals) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_creation += other.cache_creation;
+        self.cache_read += other.cache_read;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.input + self.output + self.cache_creation + self.cache_read
+    }
+}
+
+/// Prices in USD per million tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default)]
+    pub cache_write: f64,
+    #[serde(default)]
+    pub cache_read: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, tokens: &TokenTotals) -> f64 {
+        (tokens.input as f64 * self.input
+            + tokens.output as f64 * self.output
+            + tokens.cache_creation as f64 * self.cache_write
+            + tokens.cache_read as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Maps model name patterns to prices. A model uses the price of the longest
+/// pattern that is contained in its name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceTable {
+    pub prices: HashMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let mut prices = HashMap::new();
+        prices.insert(
+            "opus".to_string(),
+            ModelPrice {
+                input: 15.0,
+                output: 75.0,
+                cache_write: 18.75,
+                cache_read: 1.5,
+            },
+        );
+        prices.insert(
+            "sonnet".to_string(),
+            ModelPrice {
+                input: 3.0,
+                output: 15.0,
+                cache_write: 3.75,
+                cache_read: 0.3,
+            },
+        );
+        prices.insert(
+            "haiku".to_string(),
+            ModelPrice {
+                input: 0.8,
+                output: 4.0,
+                cache_write: 1.0,
+                cache_read: 0.08,
+            },
+        );
+        Self { prices }
+    }
+}
+
+impl PriceTable {
+    /// Load a JSON object of `pattern -> price` from a file. Entries override
+    /// the built-in defaults with the same pattern.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        let overrides: HashMap<String, ModelPrice> = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid price table {}: {}", path.display(), e))?;
+
+        let mut table = Self::default();
+        table.prices.extend(overrides);
+        Ok(table)
+    }
+
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        let model = model.to_lowercase();
+        self.prices
+            .iter()
+            .filter(|(pattern, _)| model.contains(&pattern.to_lowercase()))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, price)| price)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelUsage {
+    pub model: String,
+    pub tokens: TokenTotals,
+    pub responses: usize,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatStats {
+    pub by_model: Vec<ModelUsage>,
+    pub total: TokenTotals,
+    pub total_cost: f64,
+    pub has_unpriced_models: bool,
+}
+
+/// Sum token usage per model over a chat's messages.
+///
+/// Claude Code writes one line per content block of an API response, each
+/// repeating the same usage, so responses are counted once per message id
+/// (or request id when the message id is missing).
+pub fn compute_chat_stats<'a, I>(messages: I, prices: &PriceTable) -> ChatStats
+where
+    I: IntoIterator<Item = &'a Message>,
+{
+    let mut seen_responses: HashSet<&str> = HashSet::new();
+    let mut by_model: HashMap<String, ModelUsage> = HashMap::new();
+
+    for message in messages {
+        let Some(usage) = message.get_usage() else {
+            continue;
+        };
+
+        let response_id = message
+            .get_api_message_id()
+            .or(message.request_id.as_deref());
+        if let Some(response_id) = response_id {
+            if !seen_responses.insert(response_id) {
+                continue;
+            }
+        }
+
+        let model = message.get_model().unwrap_or("unknown");
+        let entry = by_model
+            .entry(model.to_string())
+            .or_insert_with(|| ModelUsage {
+                model: model.to_string(),
+                ..Default::default()
+            });
+        entry.tokens.add_usage(usage);
+        entry.responses += 1;
+    }
+
+    let mut stats = ChatStats::default();
+    for (_, mut model_usage) in by_model {
+        model_usage.cost = prices
+            .price_for(&model_usage.model)
+            .map(|price| price.cost(&model_usage.tokens));
+        match model_usage.cost {
+            Some(cost) => stats.total_cost += cost,
+            None => stats.has_unpriced_models = true,
+        }
+        stats.total.add(&model_usage.tokens);
+        stats.by_model.push(model_usage);
+    }
+
+    stats
+        .by_model
+        .sort_by_key(|model_usage| std::cmp::Reverse(model_usage.tokens.total()));
+    stats
+}
+
+/// Sum token usage over messages regardless of model, counting each API
+/// response once.
+pub fn total_usage<'a, I>(messages: I) -> TokenTotals
+where
+    I: IntoIterator<Item = &'a Message>,
+{
+    let no_prices = PriceTable {
+        prices: HashMap::new(),
+    };
+    compute_chat_stats(messages, &no_prices).total
+}
+
+/// Format a token count with thousands separators, e.g. `1,234,567`.
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    // Groups of three end where the number does
+    let group_start = digits.len() % 3;
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && i % 3 == group_start {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+pub fn format_cost(cost: f64) -> String {
+    if cost < 1.0 {
+        format!("${:.4}", cost)
+    } else {
+        format!("${:.2}", cost)
+    }
+}
@@ -0,0 +1,516 @@
+use crate::highlight::{
+    highlight_code, highlight_content, highlight_numbered_code, language_from_path,
+};
+use crate::project::{ContentBlock, HierarchicalMessage, Message};
+use chrono::{DateTime, Utc};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// Where the tool calls and tool results of a chat are, by tool use id.
+#[derive(Debug, Default)]
+pub struct ToolCallIndex {
+    pub calls: HashMap<String, usize>,   // Tool use id -> message index
+    pub results: HashMap<String, usize>, // Tool use id -> message index
+}
+
+impl ToolCallIndex {
+    pub fn build(messages: &[HierarchicalMessage]) -> Self {
+        let mut index = Self::default();
+        for (message_index, hierarchical) in messages.iter().enumerate() {
+            for block in hierarchical.message.content_blocks() {
+                match block {
+                    ContentBlock::ToolUse { id: Some(id), .. } => {
+                        index.calls.insert(id, message_index);
+                    }
+                    ContentBlock::ToolResult {
+                        tool_use_id: Some(id),
+                        ..
+                    } => {
+                        index.results.insert(id, message_index);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        index
+    }
+
+    /// Index of the message with the other half of the message's first
+    /// tool call or result.
+    pub fn counterpart(&self, message: &Message) -> Option<usize> {
+        message
+            .content_blocks()
+            .into_iter()
+            .find_map(|block| match block {
+                ContentBlock::ToolUse { id: Some(id), .. } => self.results.get(&id).copied(),
+                ContentBlock::ToolResult {
+                    tool_use_id: Some(id),
+                    ..
+                } => self.calls.get(&id).copied(),
+                _ => None,
+            })
+    }
+
+    /// Names of the tools the message calls, or whose results it holds.
+    pub fn tool_names(&self, messages: &[HierarchicalMessage], message: &Message) -> Vec<String> {
+        message
+            .content_blocks()
+            .into_iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { name, .. } => Some(name),
+                ContentBlock::ToolResult {
+                    tool_use_id: Some(id),
+                    ..
+                } => {
+                    let index = *self.calls.get(&id)?;
+                    find_call(messages, index, &id).map(|call| call.name)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+struct ToolCall {
+    name: String,
+    input: Value,
+    timestamp: DateTime<Utc>,
+}
+
+struct ToolResult {
+    content: String,
+    is_error: bool,
+    timestamp: DateTime<Utc>,
+}
+
+fn find_call(messages: &[HierarchicalMessage], index: usize, id: &str) -> Option<ToolCall> {
+    let message = &messages.get(index)?.message;
+    message
+        .content_blocks()
+        .into_iter()
+        .find_map(|block| match block {
+            ContentBlock::ToolUse {
+                id: Some(call_id),
+                name,
+                input,
+            } if call_id == id => Some(ToolCall {
+                name,
+                input,
+                timestamp: message.timestamp,
+            }),
+            _ => None,
+        })
+}
+
+fn find_result(messages: &[HierarchicalMessage], index: usize, id: &str) -> Option<ToolResult> {
+    let message = &messages.get(index)?.message;
+    message
+        .content_blocks()
+        .into_iter()
+        .find_map(|block| match block {
+            ContentBlock::ToolResult {
+                tool_use_id: Some(result_id),
+                content,
+                is_error,
+            } if result_id == id => Some(ToolResult {
+                content,
+                is_error,
+                timestamp: message.timestamp,
+            }),
+            _ => None,
+        })
+}
+
+/// Human readable duration between a call and its result.
+pub fn format_duration(started: DateTime<Utc>, ended: DateTime<Utc>) -> String {
+    let millis = (ended - started).num_milliseconds().max(0);
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if millis < 60_000 {
+        format!("{:.1}s", millis as f64 / 1000.0)
+    } else {
+        format!("{}m {}s", millis / 60_000, (millis % 60_000) / 1000)
+    }
+}
+
+/// Language of the file a tool call works on, if any.
+pub fn input_language(input: &Value) -> Option<String> {
+    input
+        .get("file_path")
+        .or_else(|| input.get("notebook_path"))
+        .and_then(|path| path.as_str())
+        .and_then(language_from_path)
+}
+
+fn input_lines(input: &Value) -> Vec<Line<'static>> {
+    let Value::Object(fields) = input else {
+        return vec![Line::from(format!("  {}", input))];
+    };
+
+    let mut lines = Vec::new();
+    for (key, value) in fields {
+        match value {
+            // Multi-line strings (file contents, commands, ...) go below their key
+            Value::String(text) if text.contains('\n') => {
+                lines.push(Line::styled(
+                    format!("  {}:", key),
+                    Style::default().fg(Color::Cyan),
+                ));
+                lines.extend(text.lines().map(|line| Line::from(format!("    {}", line))));
+            }
+            // Objects and arrays go below their key as pretty-printed JSON
+            Value::Object(_) | Value::Array(_) => {
+                let json = serde_json::to_string_pretty(value).unwrap_or_default();
+                let json: Vec<&str> = json.lines().collect();
+                lines.push(Line::styled(
+                    format!("  {}:", key),
+                    Style::default().fg(Color::Cyan),
+                ));
+                for mut line in highlight_code(&json, Some("json")) {
+                    line.spans.insert(0, Span::raw("    "));
+                    lines.push(line);
+                }
+            }
+            _ => {
+                let mut line = Line::from(Span::styled(
+                    format!("  {}: ", key),
+                    Style::default().fg(Color::Cyan),
+                ));
+                line.spans.extend(json_value_spans(value));
+                lines.push(line);
+            }
+        }
+    }
+    lines
+}
+
+/// A value as single-line JSON, colored.
+fn json_value_spans(value: &Value) -> Vec<Span<'static>> {
+    let json = serde_json::to_string(value).unwrap_or_default();
+    highlight_code(&[&json], Some("json"))
+        .pop()
+        .map(|line| line.spans)
+        .unwrap_or_default()
+}
+
+/// Line-based unified diff of two texts, without hunk headers.
+pub fn diff_lines(old: &str, new: &str) -> Vec<Line<'static>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let removed_style = Style::default().fg(Color::Red);
+    let added_style = Style::default().fg(Color::Green);
+
+    // Longest common subsequence table, skipped for huge inputs where
+    // showing everything as replaced is good enough
+    let use_common = old.len() * new.len() <= MAX_DIFF_CELLS;
+    let mut common = Vec::new();
+    if use_common {
+        common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                common[i][j] = if old[i] == new[j] {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(Line::from(format!("  {}", old[i])));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || !use_common || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(Line::styled(format!("- {}", old[i]), removed_style));
+            i += 1;
+        } else {
+            lines.push(Line::styled(format!("+ {}", new[j]), added_style));
+            j += 1;
+        }
+    }
+    lines
+}
+
+fn field_line(key: &str, value: impl Into<String>) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("  {}: ", key), Style::default().fg(Color::Cyan)),
+        Span::raw(value.into()),
+    ])
+}
+
+fn todo_lines(todos: &[Value]) -> Vec<Line<'static>> {
+    todos
+        .iter()
+        .map(|todo| {
+            let content = todo.get("content").and_then(Value::as_str).unwrap_or("");
+            let (checkbox, style) = match todo.get("status").and_then(Value::as_str) {
+                Some("completed") => ("☑", Style::default().fg(Color::Green)),
+                Some("in_progress") => ("◐", Style::default().fg(Color::Yellow)),
+                _ => ("☐", Style::default()),
+            };
+            Line::styled(format!("  {} {}", checkbox, content), style)
+        })
+        .collect()
+}
+
+/// Lines describing a tool call's input, specialized for the common
+/// Claude Code tools. Other tools get a plain parameter list.
+fn render_tool_input(name: &str, input: &Value) -> Vec<Line<'static>> {
+    let str_field = |key: &str| input.get(key).and_then(Value::as_str);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    match name {
+        "Edit" => {
+            if let (Some(path), Some(old), Some(new)) = (
+                str_field("file_path"),
+                str_field("old_string"),
+                str_field("new_string"),
+            ) {
+                let mut lines = vec![field_line("file", path)];
+                if input.get("replace_all").and_then(Value::as_bool) == Some(true) {
+                    lines.push(Line::styled("  (all occurrences)", dim));
+                }
+                lines.extend(diff_lines(old, new));
+                return lines;
+            }
+        }
+        "MultiEdit" => {
+            if let (Some(path), Some(edits)) = (
+                str_field("file_path"),
+                input.get("edits").and_then(Value::as_array),
+            ) {
+                let mut lines = vec![field_line("file", path)];
+                for (number, edit) in edits.iter().enumerate() {
+                    let text = |key: &str| edit.get(key).and_then(Value::as_str).unwrap_or("");
+                    lines.push(Line::styled(
+                        format!("  @@ edit {}/{} @@", number + 1, edits.len()),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                    lines.extend(diff_lines(text("old_string"), text("new_string")));
+                }
+                return lines;
+            }
+        }
+        "Write" => {
+            if let (Some(path), Some(content)) = (str_field("file_path"), str_field("content")) {
+                let content: Vec<&str> = content.lines().collect();
+                let mut lines = vec![field_line(
+                    "file",
+                    format!("{} ({} lines)", path, content.len()),
+                )];
+                lines.extend(highlight_code(&content, input_language(input).as_deref()));
+                return lines;
+            }
+        }
+        "Bash" => {
+            if let Some(command) = str_field("command") {
+                let mut lines = Vec::new();
+                if let Some(description) = str_field("description") {
+                    lines.push(Line::styled(format!("  # {}", description), dim));
+                }
+                let command: Vec<&str> = command.lines().collect();
+                for (number, mut line) in
+                    highlight_code(&command, Some("sh")).into_iter().enumerate()
+                {
+                    let prompt = if number == 0 { "  $ " } else { "    " };
+                    line.spans.insert(0, Span::styled(prompt, dim));
+                    lines.push(line);
+                }
+                return lines;
+            }
+        }
+        "Read" => {
+            if let Some(path) = str_field("file_path") {
+                let offset = input.get("offset").and_then(Value::as_u64);
+                let limit = input.get("limit").and_then(Value::as_u64);
+                let range = match (offset, limit) {
+                    (Some(offset), Some(limit)) => {
+                        format!(" (lines {}-{})", offset, offset + limit.saturating_sub(1))
+                    }
+                    (Some(offset), None) => format!(" (from line {})", offset),
+                    (None, Some(limit)) => format!(" (first {} lines)", limit),
+                    (None, None) => String::new(),
+                };
+                return vec![field_line("file", format!("{}{}", path, range))];
+            }
+        }
+        "Grep" => {
+            if let Some(pattern) = str_field("pattern") {
+                let mut lines = vec![field_line("pattern", format!("/{}/", pattern))];
+                for key in ["path", "glob", "type", "output_mode"] {
+                    if let Some(value) = str_field(key) {
+                        lines.push(field_line(key, value));
+                    }
+                }
+                let flags: Vec<&str> = ["-i", "-n", "multiline"]
+                    .into_iter()
+                    .filter(|flag| input.get(*flag).and_then(Value::as_bool) == Some(true))
+                    .collect();
+                if !flags.is_empty() {
+                    lines.push(field_line("flags", flags.join(" ")));
+                }
+                return lines;
+            }
+        }
+        "TodoWrite" => {
+            if let Some(todos) = input.get("todos").and_then(Value::as_array) {
+                return todo_lines(todos);
+            }
+        }
+        _ => {}
+    }
+    input_lines(input)
+}
+
+/// Exit status of a Bash tool result. Failed commands start their output
+/// with `Exit code N`.
+fn bash_exit_status(result: &ToolResult) -> String {
+    let code = result
+        .content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("Exit code "))
+        .and_then(|code| code.trim().parse::<i32>().ok());
+    match code {
+        Some(code) => code.to_string(),
+        None if result.is_error => "error".to_string(),
+        None => "0".to_string(),
+    }
+}
+
+fn render_tool_pair(call: Option<&ToolCall>, result: Option<&ToolResult>) -> Vec<Line<'static>> {
+    let header_style = Style::default()
+        .fg(Color::Magenta)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = Vec::new();
+
+    match call {
+        Some(call) => {
+            lines.push(Line::styled(format!("▶ Tool: {}", call.name), header_style));
+            lines.extend(render_tool_input(&call.name, &call.input));
+        }
+        None => lines.push(Line::styled("▶ Tool: (call not found)", header_style)),
+    }
+
+    let Some(result) = result else {
+        lines.push(Line::styled(
+            "◀ No result yet",
+            Style::default().fg(Color::DarkGray),
+        ));
+        return lines;
+    };
+
+    let tool_name = call.map(|call| call.name.as_str());
+    let mut header = if tool_name == Some("Bash") {
+        format!("◀ Output (exit status {})", bash_exit_status(result))
+    } else if result.is_error {
+        "◀ Result (error)".to_string()
+    } else {
+        "◀ Result".to_string()
+    };
+    if let Some(call) = call {
+        header.push_str(&format!(
+            ", {}",
+            format_duration(call.timestamp, result.timestamp)
+        ));
+    }
+    let result_style = if result.is_error {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        header_style
+    };
+    lines.push(Line::styled(header, result_style));
+
+    let language = call.and_then(|call| input_language(&call.input));
+    let content: Vec<&str> = result.content.lines().collect();
+    lines.extend(highlight_numbered_code(&content, language.as_deref()));
+    lines
+}
+
+/// Lines of a message's content for the details pane. Tool calls are shown
+/// together with their results, wherever in the chat those are.
+pub fn render_message_content(
+    message: &Message,
+    messages: &[HierarchicalMessage],
+    tool_calls: &ToolCallIndex,
+    markdown: bool,
+) -> Vec<Line<'static>> {
+    let blocks = message.content_blocks();
+    if blocks.is_empty() {
+        return highlight_content(&message.get_detailed_content(), None, false);
+    }
+
+    // Unlabeled code blocks are probably in the language of the file being worked on
+    let language_hint = blocks.iter().find_map(|block| match block {
+        ContentBlock::ToolUse { input, .. } => input_language(input),
+        _ => None,
+    });
+
+    let mut lines = Vec::new();
+    for block in blocks {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        match block {
+            ContentBlock::Text(text) => {
+                lines.extend(highlight_content(&text, language_hint.as_deref(), markdown));
+            }
+            ContentBlock::Thinking(thinking) => {
+                let style = Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC);
+                lines.push(Line::styled("[Thinking]", style));
+                lines.extend(
+                    thinking
+                        .lines()
+                        .map(|line| Line::styled(line.to_string(), style)),
+                );
+            }
+            ContentBlock::ToolUse { id, name, input } => {
+                let result = id.as_deref().and_then(|id| {
+                    let index = *tool_calls.results.get(id)?;
+                    find_result(messages, index, id)
+                });
+                let call = ToolCall {
+                    name,
+                    input,
+                    timestamp: message.timestamp,
+                };
+                lines.extend(render_tool_pair(Some(&call), result.as_ref()));
+            }
+            ContentBlock::Image(image) => {
+                lines.push(Line::styled(
+                    image.placeholder(),
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                let call = tool_use_id.as_deref().and_then(|id| {
+                    let index = *tool_calls.calls.get(id)?;
+                    find_call(messages, index, id)
+                });
+                let result = ToolResult {
+                    content,
+                    is_error,
+                    timestamp: message.timestamp,
+                };
+                lines.extend(render_tool_pair(call.as_ref(), Some(&result)));
+            }
+        }
+    }
+    lines
+}
@@ -1,17 +1,28 @@
-use crate::app::{App, Screen};
+use crate::app::{App, Screen, SidechainFilter};
+use crate::matcher::Matcher;
+use crate::project::{HierarchicalMessage, MessageCategory};
+use crate::stats::{format_cost, format_count};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Margin},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{
+        Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    },
     Frame,
 };
+use std::ops::Range;
 
 pub fn render(f: &mut Frame, app: &mut App) {
+    if app.viewer.is_some() {
+        render_viewer(f, app);
+        return;
+    }
     match app.screen {
         Screen::Projects => render_projects(f, app),
         Screen::Chats => render_chats(f, app),
         Screen::Messages => render_messages(f, app),
+        Screen::SearchResults => render_search_results(f, app),
     }
 }
 
@@ -22,7 +33,9 @@ fn render_projects(f: &mut Frame, app: &mut App) {
         .split(f.area());
 
     // Calculate available width for project names
-    let reserved_width = 20 + 5 + 6; // 6 for spacing and borders
+    // 20 for date, 5 for chat count, 13 for messages, 16 for tokens, 22 for activity range,
+    // 10 for spacing and borders
+    let reserved_width = 20 + 5 + 13 + 16 + 22 + 10;
     let available_name_width = (chunks[0].width as usize).saturating_sub(reserved_width);
 
     // First pass: truncate all project names and find max width
@@ -39,6 +52,7 @@ fn render_projects(f: &mut Frame, app: &mut App) {
         .unwrap_or(0);
 
     // Second pass: create list items with consistent padding
+    let matcher = app.highlight_matcher();
     let projects: Vec<ListItem> = active_projects
         .iter()
         .enumerate()
@@ -46,8 +60,25 @@ fn render_projects(f: &mut Frame, app: &mut App) {
             let date_str = project.last_modified.format("%Y-%m-%d %H:%M").to_string();
             let padded_name = format!("{:<width$}", truncated_names[i], width = max_name_width);
 
-            let content = format!("{} {:<20} {:>5}", padded_name, date_str, project.chat_count);
-            ListItem::new(Line::from(vec![Span::raw(content)]))
+            let activity_range = match (project.first_activity, project.last_activity) {
+                (Some(first), Some(last)) => {
+                    format!("{}..{}", first.format("%Y-%m-%d"), last.format("%Y-%m-%d"))
+                }
+                _ => String::new(),
+            };
+
+            let content = format!(
+                "{} {:<20} {:>5} {:>8} msgs {:>11} tok  {}",
+                padded_name,
+                date_str,
+                project.chat_count,
+                format_count(project.message_count as u64),
+                format_count(project.tokens.total()),
+                activity_range
+            );
+            let highlights =
+                truncated_highlights(matcher.as_ref(), &project.name, &truncated_names[i], 0);
+            ListItem::new(highlight_line(Line::raw(content), &highlights))
         })
         .collect();
 
@@ -69,6 +100,10 @@ fn render_projects(f: &mut Frame, app: &mut App) {
                     Span::raw(" to select, "),
                     Span::styled("/", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" search, "),
+                    Span::styled("F", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" find in chats, "),
+                    Span::styled("r", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" refresh, "),
                     Span::styled("h/Esc", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" back, "),
                     Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
@@ -84,12 +119,20 @@ fn render_projects(f: &mut Frame, app: &mut App) {
 
     f.render_stateful_widget(list, chunks[0], &mut app.projects.state);
 
-    let status_text = if app.search_mode {
-        format!("/{}", app.search_query)
+    let status_text = if app.content_search_mode {
+        format!("Find in all projects: {}", app.content_search_query)
+    } else if app.search_mode {
+        app.search_status()
     } else if app.projects.is_empty() {
         "No projects found".to_string()
     } else {
-        "Project list".to_string()
+        let (message_count, tokens) = app.global_totals();
+        format!(
+            "Project list: {} projects, {} messages, {} tokens",
+            app.projects.items.len(),
+            format_count(message_count as u64),
+            format_count(tokens.total())
+        )
     };
 
     let status =
@@ -121,6 +164,7 @@ fn render_chats(f: &mut Frame, app: &mut App) {
         .unwrap_or(0);
 
     // Second pass: create list items with consistent padding
+    let matcher = app.highlight_matcher();
     let chats: Vec<ListItem> = active_chats
         .iter()
         .enumerate()
@@ -129,7 +173,9 @@ fn render_chats(f: &mut Frame, app: &mut App) {
             let padded_name = format!("{:<width$}", truncated_names[i], width = max_name_width);
 
             let content = format!("{} {:<20} {:>8}", padded_name, date_str, chat.message_count);
-            ListItem::new(Line::from(vec![Span::raw(content)]))
+            let highlights =
+                truncated_highlights(matcher.as_ref(), &chat.name, &truncated_names[i], 0);
+            ListItem::new(highlight_line(Line::raw(content), &highlights))
         })
         .collect();
 
@@ -151,6 +197,10 @@ fn render_chats(f: &mut Frame, app: &mut App) {
                     Span::raw(" to select, "),
                     Span::styled("/", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" search, "),
+                    Span::styled("F", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" find in chats, "),
+                    Span::styled("r", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" refresh, "),
                     Span::styled("h/Esc", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" back, "),
                     Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
@@ -171,8 +221,10 @@ fn render_chats(f: &mut Frame, app: &mut App) {
         .map(|p| p.name.as_str())
         .unwrap_or("Unknown");
 
-    let status_text = if app.search_mode {
-        format!("/{}", app.search_query)
+    let status_text = if app.content_search_mode {
+        format!("Find in {}: {}", project_name, app.content_search_query)
+    } else if app.search_mode {
+        app.search_status()
     } else if app.chats.is_empty() {
         "No chats found".to_string()
     } else {
@@ -190,6 +242,15 @@ fn render_messages(f: &mut Frame, app: &mut App) {
         .constraints([Constraint::Min(1), Constraint::Length(1)])
         .split(f.area());
 
+    // Reserve space for the usage stats pane at the bottom when it's shown
+    let stats_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(app.stats_pane_height()),
+        ])
+        .split(main_chunks[0]);
+
     // Split the main content area into two panes: list and details
     let direction = if app.vertical_split {
         Direction::Vertical
@@ -199,7 +260,7 @@ fn render_messages(f: &mut Frame, app: &mut App) {
     let content_chunks = Layout::default()
         .direction(direction)
         .constraints([Constraint::Min(30), Constraint::Min(30)])
-        .split(main_chunks[0]);
+        .split(stats_chunks[0]);
 
     // Render message list in first pane (left or top)
     render_message_list(f, app, content_chunks[0]);
@@ -207,6 +268,10 @@ fn render_messages(f: &mut Frame, app: &mut App) {
     // Render message details in second pane (right or bottom)
     render_message_details(f, app, content_chunks[1]);
 
+    if app.show_stats {
+        render_usage_stats(f, app, stats_chunks[1]);
+    }
+
     // Render status line
     let project_name = app
         .selected_project()
@@ -218,12 +283,23 @@ fn render_messages(f: &mut Frame, app: &mut App) {
         .unwrap_or("Unknown");
 
     let status_text = if app.search_mode {
-        format!("/{}", app.search_query)
+        app.search_status()
+    } else if app.find_mode {
+        app.find_status()
+    } else if let Some(status_message) = &app.status_message {
+        status_message.clone()
+    } else if app.find_active() {
+        app.find_status()
     } else if app.messages.is_empty() {
         "No messages found".to_string()
     } else {
         format!("{} > {} > Messages", project_name, chat_name)
     };
+    let status_text = match (app.follow_mode, app.follow_pinned) {
+        (true, true) => format!("{} [following, pinned]", status_text),
+        (true, false) => format!("{} [following]", status_text),
+        _ => status_text,
+    };
 
     let status =
         Paragraph::new(status_text).style(Style::default().fg(Color::White).bg(Color::Blue));
@@ -231,6 +307,7 @@ fn render_messages(f: &mut Frame, app: &mut App) {
 }
 
 fn render_message_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
+    let matcher = app.highlight_matcher();
     let active_messages = app.messages.active_items();
     let messages: Vec<ListItem> = active_messages
         .iter()
@@ -245,52 +322,64 @@ fn render_message_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                     "user" => "U",
                     "assistant" => "A",
                     "system" => "S",
-                    _ if role.contains("PostToolUse") || role.contains("Hook") => "H",
-                    _ if content_text.contains("PostToolUse") || content_text.contains("Hook") => {
-                        "H"
-                    }
+                    _ if message.is_hook() => "H",
                     _ => "?",
                 };
 
-                // Create indentation: initial messages at 0, related messages at 2 spaces
-                let (indent, connector) = if hierarchical_message.is_initial {
-                    (String::new(), String::new())
+                // Tree guides and connector, with the number of hidden messages
+                // after collapsed ones
+                let mut prefix = if app.active_path_only {
+                    // The active path is a single chain per root
+                    let chain_goes_on = active_messages
+                        .get(filtered_index + 1)
+                        .is_some_and(|next| !next.is_initial);
+                    match (hierarchical_message.is_initial, chain_goes_on) {
+                        (true, _) => String::new(),
+                        (false, true) => "  ├─".to_string(),
+                        (false, false) => "  └─".to_string(),
+                    }
                 } else {
-                    (
-                        "  ".to_string(),
-                        if hierarchical_message.has_continuation {
-                            "├─".to_string()
-                        } else {
-                            "└─".to_string()
-                        },
-                    )
+                    hierarchical_message.tree_prefix.clone()
                 };
+                let collapsed = app.is_collapsed(hierarchical_message);
+                if collapsed {
+                    // Nothing below continues this chain while it's collapsed
+                    if let Some(guide) = prefix.strip_suffix("├─") {
+                        prefix = format!("{}└─", guide);
+                    }
+                }
+                if let Some((position, count)) = hierarchical_message.branch {
+                    prefix.push_str(&format!("[branch {}/{}] ", position, count));
+                }
+                if collapsed {
+                    prefix.push_str(&format!("▸ +{} ", hierarchical_message.descendant_count));
+                }
 
                 // Calculate available width for message content
-                let total_prefix_len = indent.len() + connector.len();
+                let total_prefix_len = prefix.chars().count();
                 // Reserve space for: message number (3), role (1), spacing (4)
                 let reserved_width = 3 + 1 + 4 + total_prefix_len;
                 let available_width = (area.width as usize).saturating_sub(reserved_width);
 
                 let original_message_number = app.messages.original_index(filtered_index) + 1;
-                let content_with_indent = format!(
-                    "{:<3} {} {}{}{}",
-                    original_message_number,
-                    role_display,
-                    indent,
-                    connector,
-                    truncate_string(&content_text, available_width)
-                );
-
-                // Style initial messages in bold
-                if hierarchical_message.is_initial {
-                    ListItem::new(Line::from(vec![Span::styled(
-                        content_with_indent,
-                        Style::default().add_modifier(Modifier::BOLD),
-                    )]))
+                let head = format!("{:<3} {} {}", original_message_number, role_display, prefix);
+                let shown_text = truncate_string(&content_text, available_width);
+                let highlights =
+                    truncated_highlights(matcher.as_ref(), &content_text, &shown_text, head.len());
+                let content_with_indent = format!("{}{}", head, shown_text);
+
+                // Style initial messages in bold, and set sidechains apart
+                let style = if hierarchical_message.is_initial {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else if message.is_sidechain == Some(true) {
+                    Style::default().fg(Color::Magenta)
                 } else {
-                    ListItem::new(Line::from(vec![Span::raw(content_with_indent)]))
-                }
+                    Style::default()
+                };
+                ListItem::new(highlight_line(
+                    Line::styled(content_with_indent, style),
+                    &highlights,
+                ))
             })
             .unwrap_or_else(|_| {
                 // If there's a panic, create an error message item
@@ -304,21 +393,62 @@ fn render_message_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         })
         .collect();
 
+    let mut views = Vec::new();
+    if app.active_path_only {
+        views.push("active path");
+    }
+    match app.sidechain_filter {
+        SidechainFilter::All => {}
+        SidechainFilter::Hide => views.push("sidechains hidden"),
+        SidechainFilter::Only => views.push("sidechains only"),
+    }
+    // Listed in the order of their keys
+    let hidden_categories: Vec<&str> = MessageCategory::ALL
+        .iter()
+        .filter(|category| app.hidden_categories.contains(category))
+        .map(|category| category.label())
+        .collect();
+    let hiding = format!("hiding {}", hidden_categories.join(", "));
+    if !hidden_categories.is_empty() {
+        views.push(&hiding);
+    }
+    let title = if views.is_empty() {
+        "Messages".to_string()
+    } else {
+        format!("Messages ({})", views.join(", "))
+    };
+
     let list = List::new(messages)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Messages")
+                .title(title)
                 .title_bottom(Line::from(vec![
                     Span::raw("Use "),
                     Span::styled("↑↓/jk", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" navigate, "),
                     Span::styled("J/K", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" initial msgs, "),
+                    Span::styled("c/C", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" collapse/expand all, "),
+                    Span::styled("[/]", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" branches, "),
+                    Span::styled("1-7", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" hide types, "),
+                    Span::styled("Tab", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" focus details, "),
                     Span::styled("/", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" search, "),
+                    Span::styled("f", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" find, "),
+                    Span::styled("n/N", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" next/prev match, "),
                     Span::styled("s", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" toggle split, "),
+                    Span::styled("u", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" usage, "),
+                    Span::styled("e/E", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" export, "),
                     Span::styled("h/Esc", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" back, "),
                     Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
@@ -336,137 +466,569 @@ fn render_message_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect
 }
 
 fn render_message_details(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
-    if let Some(selected_message) = app.selected_message() {
-        let message = &selected_message.message;
+    let border_style = if app.details_focused() {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+
+    let mut content_lines = app.selected_content_lines().to_vec();
+    if let Some(matcher) = app.highlight_matcher() {
+        let mut match_index = 0;
+        content_lines = content_lines
+            .into_iter()
+            .map(|line| {
+                let highlights: Vec<(Range<usize>, Style)> = matcher
+                    .occurrences(&line.to_string())
+                    .into_iter()
+                    .map(|range| {
+                        let style = if app.details_match == Some(match_index) {
+                            current_match_style()
+                        } else {
+                            match_style()
+                        };
+                        match_index += 1;
+                        (range, style)
+                    })
+                    .collect();
+                highlight_line(line, &highlights)
+            })
+            .collect();
+    }
+    let content_line_count = content_lines.len();
+    let Some(selected_message) = app.selected_message() else {
+        // Show placeholder when no message is selected
+        app.set_details_layout(None, 0, 0);
+        let placeholder = Paragraph::new("No message selected")
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title("Details"),
+            )
+            .style(Style::default().fg(Color::DarkGray));
+
+        f.render_widget(placeholder, area);
+        return;
+    };
+
+    let uuid = selected_message.message.uuid.clone();
+    let lines = message_detail_lines(selected_message, content_lines);
+    let content_start = lines.len() - content_line_count;
+    // Lines aren't trimmed when wrapping to keep the indentation of code
+    let wrap = ratatui::widgets::Wrap { trim: false };
+
+    // Borders take one line/column on each side
+    let width = area.width.saturating_sub(2);
+    let match_row = app.details_match_line.take().map(|line| {
+        Paragraph::new(lines[..content_start + line].to_vec())
+            .wrap(wrap)
+            .line_count(width)
+    });
+    let paragraph = Paragraph::new(lines).wrap(wrap);
+    let line_count = paragraph.line_count(width);
+    let height = area.height.saturating_sub(2) as usize;
+    app.set_details_layout(Some(&uuid), line_count, height);
+    if let Some(row) = match_row {
+        // Keep the match a third of the way down the pane
+        app.details_scroll = row
+            .saturating_sub(height / 3)
+            .min(line_count.saturating_sub(height));
+    }
+
+    let first_line = (app.details_scroll + 1).min(line_count);
+    let last_line = (app.details_scroll + height).min(line_count);
+    let position = format!(" {}-{}/{} ", first_line, last_line, line_count);
+    let title = if app.raw_markdown {
+        "Details (raw)"
+    } else {
+        "Details"
+    };
 
-        let mut details = Vec::new();
+    let paragraph = paragraph
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .title(title)
+                .title_bottom(Line::from(position).right_aligned()),
+        )
+        .scroll((app.details_scroll.min(u16::MAX as usize) as u16, 0));
+    f.render_widget(paragraph, area);
+
+    if line_count > height {
+        let mut scrollbar_state =
+            ScrollbarState::new(line_count - height).position(app.details_scroll);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+/// Everything shown about a message in the details pane, unwrapped.
+fn message_detail_lines(
+    selected_message: &HierarchicalMessage,
+    content_lines: Vec<Line<'static>>,
+) -> Vec<Line<'static>> {
+    let message = &selected_message.message;
+
+    let mut details = Vec::new();
+
+    // Header with basic info
+    details.push(Line::from(vec![Span::styled(
+        "Message Details",
+        Style::default().add_modifier(Modifier::BOLD),
+    )]));
+    details.push(Line::from("".to_string()));
 
-        // Header with basic info
+    // UUID and timestamp
+    details.push(Line::from(format!("UUID: {}", message.uuid)));
+    details.push(Line::from(format!(
+        "Time: {}",
+        message.timestamp.format("%Y-%m-%d %H:%M:%S")
+    )));
+    details.push(Line::from(format!("Type: {}", message.msg_type)));
+    details.push(Line::from(format!("Role: {}", message.get_role())));
+
+    // Hierarchical info
+    if selected_message.is_initial {
+        details.push(Line::from("Status: Initial message"));
+    } else {
+        details.push(Line::from(format!(
+            "Depth: {} (chain message)",
+            selected_message.chain_depth
+        )));
+    }
+
+    if let Some(parent) = &message.parent_uuid {
+        details.push(Line::from(format!(
+            "Parent: {}",
+            truncate_string(parent, 36)
+        )));
+    }
+
+    details.push(Line::from("".to_string()));
+
+    // Metadata section
+    if message.user_type.is_some() || message.cwd.is_some() || message.version.is_some() {
         details.push(Line::from(vec![Span::styled(
-            "Message Details",
+            "Metadata:",
             Style::default().add_modifier(Modifier::BOLD),
         )]));
+
+        if let Some(user_type) = &message.user_type {
+            details.push(Line::from(format!("User Type: {}", user_type)));
+        }
+        if let Some(cwd) = &message.cwd {
+            details.push(Line::from(format!("Working Dir: {}", cwd)));
+        }
+        if let Some(version) = &message.version {
+            details.push(Line::from(format!("Version: {}", version)));
+        }
+        if let Some(git_branch) = &message.git_branch {
+            details.push(Line::from(format!("Git Branch: {}", git_branch)));
+        }
+        if let Some(is_meta) = &message.is_meta {
+            details.push(Line::from(format!("Meta: {}", is_meta)));
+        }
         details.push(Line::from("".to_string()));
+    }
 
-        // UUID and timestamp
-        details.push(Line::from(format!("UUID: {}", message.uuid)));
-        details.push(Line::from(format!(
-            "Time: {}",
-            message.timestamp.format("%Y-%m-%d %H:%M:%S")
-        )));
-        details.push(Line::from(format!("Type: {}", message.msg_type)));
-        details.push(Line::from(format!("Role: {}", message.get_role())));
+    // Assistant-specific info
+    let model = message.get_model();
+    let usage = message.get_usage();
+    if model.is_some() || usage.is_some() {
+        details.push(Line::from(vec![Span::styled(
+            "Assistant Info:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
 
-        // Hierarchical info
-        if selected_message.is_initial {
-            details.push(Line::from("Status: Initial message"));
-        } else {
+        if let Some(model) = model {
+            details.push(Line::from(format!("Model: {}", model)));
+        }
+        if let Some(request_id) = &message.request_id {
             details.push(Line::from(format!(
-                "Depth: {} (chain message)",
-                selected_message.chain_depth
+                "Request ID: {}",
+                truncate_string(request_id, 30)
             )));
         }
-
-        if let Some(parent) = &message.parent_uuid {
+        if let Some(message_id) = message.get_api_message_id() {
             details.push(Line::from(format!(
-                "Parent: {}",
-                truncate_string(parent, 36)
+                "Message ID: {}",
+                truncate_string(message_id, 30)
             )));
         }
-
-        details.push(Line::from("".to_string()));
-
-        // Metadata section
-        if message.user_type.is_some() || message.cwd.is_some() || message.version.is_some() {
-            details.push(Line::from(vec![Span::styled(
-                "Metadata:",
-                Style::default().add_modifier(Modifier::BOLD),
-            )]));
-
-            if let Some(user_type) = &message.user_type {
-                details.push(Line::from(format!("User Type: {}", user_type)));
+        if let Some(stop_reason) = message.get_stop_reason() {
+            details.push(Line::from(format!("Stop Reason: {}", stop_reason)));
+        }
+        if let Some(stop_sequence) = message.get_stop_sequence() {
+            details.push(Line::from(format!("Stop Sequence: {}", stop_sequence)));
+        }
+        if let Some(usage) = usage {
+            details.push(Line::from("Token Usage:"));
+            if let Some(input) = usage.input_tokens {
+                details.push(Line::from(format!("  Input: {}", input)));
             }
-            if let Some(cwd) = &message.cwd {
-                details.push(Line::from(format!("Working Dir: {}", cwd)));
+            if let Some(output) = usage.output_tokens {
+                details.push(Line::from(format!("  Output: {}", output)));
             }
-            if let Some(version) = &message.version {
-                details.push(Line::from(format!("Version: {}", version)));
+            if let Some(cache_create) = usage.cache_creation_input_tokens {
+                details.push(Line::from(format!("  Cache Create: {}", cache_create)));
             }
-            if let Some(git_branch) = &message.git_branch {
-                details.push(Line::from(format!("Git Branch: {}", git_branch)));
+            if let Some(cache_read) = usage.cache_read_input_tokens {
+                details.push(Line::from(format!("  Cache Read: {}", cache_read)));
             }
-            if let Some(is_meta) = &message.is_meta {
-                details.push(Line::from(format!("Meta: {}", is_meta)));
+            if let Some(tier) = &usage.service_tier {
+                details.push(Line::from(format!("  Service Tier: {}", tier)));
             }
-            details.push(Line::from("".to_string()));
         }
+        details.push(Line::from("".to_string()));
+    }
 
-        // Assistant-specific info
-        if message.model.is_some() || message.usage.is_some() {
-            details.push(Line::from(vec![Span::styled(
-                "Assistant Info:",
-                Style::default().add_modifier(Modifier::BOLD),
-            )]));
+    // Content section
+    details.push(Line::from(vec![Span::styled(
+        "Content:",
+        Style::default().add_modifier(Modifier::BOLD),
+    )]));
+    details.extend(content_lines);
 
-            if let Some(model) = &message.model {
-                details.push(Line::from(format!("Model: {}", model)));
-            }
-            if let Some(request_id) = &message.request_id {
-                details.push(Line::from(format!(
-                    "Request ID: {}",
-                    truncate_string(request_id, 30)
-                )));
-            }
-            if let Some(stop_reason) = &message.stop_reason {
-                details.push(Line::from(format!("Stop Reason: {}", stop_reason)));
-            }
-            if let Some(usage) = &message.usage {
-                details.push(Line::from("Token Usage:"));
-                if let Some(input) = usage.input_tokens {
-                    details.push(Line::from(format!("  Input: {}", input)));
-                }
-                if let Some(output) = usage.output_tokens {
-                    details.push(Line::from(format!("  Output: {}", output)));
-                }
-                if let Some(cache_create) = usage.cache_creation_input_tokens {
-                    details.push(Line::from(format!("  Cache Create: {}", cache_create)));
-                }
-                if let Some(cache_read) = usage.cache_read_input_tokens {
-                    details.push(Line::from(format!("  Cache Read: {}", cache_read)));
-                }
-                if let Some(tier) = &usage.service_tier {
-                    details.push(Line::from(format!("  Service Tier: {}", tier)));
-                }
-            }
-            details.push(Line::from("".to_string()));
+    details
+}
+
+fn render_viewer(f: &mut Frame, app: &mut App) {
+    let Some(viewer) = app.viewer.as_mut() else {
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(f.area());
+    let text_area = chunks[0];
+
+    // Borders take one line/column on each side
+    viewer.set_layout(
+        text_area.width.saturating_sub(2) as usize,
+        text_area.height.saturating_sub(2) as usize,
+    );
+
+    let match_style = Style::default().bg(Color::Yellow).fg(Color::Black);
+    let current_match_style = Style::default()
+        .bg(Color::LightRed)
+        .fg(Color::Black)
+        .add_modifier(Modifier::BOLD);
+
+    // Matches are ordered by line, so they can be consumed while walking the lines
+    let mut matches = viewer.matches.iter().enumerate().peekable();
+    let mut lines = Vec::with_capacity(viewer.lines.len());
+    for (line_index, line) in viewer.lines.iter().enumerate() {
+        let mut spans = Vec::new();
+        let mut position = 0;
+        while let Some((match_index, m)) = matches.next_if(|(_, m)| m.line == line_index) {
+            spans.push(Span::raw(&line[position..m.start]));
+            let style = if viewer.current_match == Some(match_index) {
+                current_match_style
+            } else {
+                match_style
+            };
+            spans.push(Span::styled(&line[m.start..m.end], style));
+            position = m.end;
         }
+        spans.push(Span::raw(&line[position..]));
+        lines.push(Line::from(spans));
+    }
 
-        // Content section
-        details.push(Line::from(vec![Span::styled(
-            "Content:",
-            Style::default().add_modifier(Modifier::BOLD),
-        )]));
-        let content_text = message.get_detailed_content();
-        let content_lines: Vec<Line> = content_text
-            .lines()
-            .take(50) // Increased limit for detailed view
-            .map(|line| Line::from(line.to_string())) // Remove truncation to allow wrapping
-            .collect();
-        details.extend(content_lines);
+    let row_count = viewer.row_count();
+    let first_row = (viewer.scroll + 1).min(row_count);
+    let last_row = (viewer.scroll + viewer.height).min(row_count);
+    let position = format!(" {}-{}/{} ", first_row, last_row, row_count);
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(viewer.title.as_str())
+                .title_bottom(Line::from(vec![
+                    Span::raw("Use "),
+                    Span::styled("↑↓/jk", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" scroll, "),
+                    Span::styled("/", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" search, "),
+                    Span::styled("n/N", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" next/prev match, "),
+                    Span::styled("v/Esc", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" close"),
+                ]))
+                .title_bottom(Line::from(position).right_aligned()),
+        )
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .scroll((viewer.scroll.min(u16::MAX as usize) as u16, 0));
+    f.render_widget(paragraph, text_area);
 
-        let paragraph = Paragraph::new(details)
-            .block(Block::default().borders(Borders::ALL).title("Details"))
-            .wrap(ratatui::widgets::Wrap { trim: true });
+    let status_text = viewer
+        .search_status()
+        .unwrap_or_else(|| viewer.title.clone());
+    let status =
+        Paragraph::new(status_text).style(Style::default().fg(Color::White).bg(Color::Blue));
+    f.render_widget(status, chunks[1]);
+}
+
+fn render_search_results(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(f.area());
+
+    // Reserve space for: message number (5), role (1), date (16), spacing and borders (7)
+    let location_width = (chunks[0].width as usize / 3).max(10);
+    let snippet_width = (chunks[0].width as usize).saturating_sub(location_width + 5 + 1 + 16 + 7);
+
+    let show_project = app.content_search_scope.is_none();
+    let results: Vec<ListItem> = app
+        .search_results
+        .active_items()
+        .iter()
+        .map(|hit| {
+            let location = if show_project {
+                format!("{}/{}", hit.project, hit.chat)
+            } else {
+                hit.chat.clone()
+            };
+            let role_display = match hit.role.as_str() {
+                "user" => "U",
+                "assistant" => "A",
+                "system" => "S",
+                _ => "?",
+            };
+            let content = format!(
+                "{:<width$} {:>5} {} {} {}",
+                truncate_from_beginning(&location, location_width),
+                format!("#{}", hit.message_index + 1),
+                role_display,
+                hit.timestamp.format("%Y-%m-%d %H:%M"),
+                truncate_string(&hit.snippet, snippet_width),
+                width = location_width
+            );
+            ListItem::new(Line::from(vec![Span::raw(content)]))
+        })
+        .collect();
 
-        f.render_widget(paragraph, area);
+    let title = match &app.content_search_scope {
+        Some(project_name) => format!("Search results in {}", project_name),
+        None => "Search results in all projects".to_string(),
+    };
+
+    let list = List::new(results)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(Line::from(vec![
+                    Span::raw("Use "),
+                    Span::styled("↑↓/jk", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to navigate, "),
+                    Span::styled("Enter/l", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" open message, "),
+                    Span::styled("/", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" filter, "),
+                    Span::styled("h/Esc", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" back, "),
+                    Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" quit"),
+                ])),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::LightBlue)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    f.render_stateful_widget(list, chunks[0], &mut app.search_results.state);
+
+    let status_text = if app.search_mode {
+        app.search_status()
+    } else if app.search_results.items.is_empty() {
+        format!("No matches for \"{}\"", app.content_search_query)
     } else {
-        // Show placeholder when no message is selected
-        let placeholder = Paragraph::new("No message selected")
-            .block(Block::default().borders(Borders::ALL).title("Details"))
-            .style(Style::default().fg(Color::DarkGray));
+        format!(
+            "{} matches for \"{}\"",
+            app.search_results.items.len(),
+            app.content_search_query
+        )
+    };
 
-        f.render_widget(placeholder, area);
+    let status =
+        Paragraph::new(status_text).style(Style::default().fg(Color::White).bg(Color::Blue));
+    f.render_widget(status, chunks[1]);
+}
+
+fn render_usage_stats(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let stats = &app.chat_stats;
+    let row = |model: &str, responses: String, tokens: &crate::stats::TokenTotals, cost: String| {
+        format!(
+            "{:<32} {:>6} {:>12} {:>12} {:>12} {:>12} {:>10}",
+            truncate_string(model, 32),
+            responses,
+            format_count(tokens.input),
+            format_count(tokens.output),
+            format_count(tokens.cache_creation),
+            format_count(tokens.cache_read),
+            cost
+        )
+    };
+
+    let mut lines = vec![Line::from(vec![Span::styled(
+        format!(
+            "{:<32} {:>6} {:>12} {:>12} {:>12} {:>12} {:>10}",
+            "Model", "Resp", "Input", "Output", "Cache Write", "Cache Read", "Cost"
+        ),
+        Style::default().add_modifier(Modifier::BOLD),
+    )])];
+
+    for model_usage in &stats.by_model {
+        let cost = model_usage
+            .cost
+            .map(format_cost)
+            .unwrap_or_else(|| "n/a".to_string());
+        lines.push(Line::from(row(
+            &model_usage.model,
+            model_usage.responses.to_string(),
+            &model_usage.tokens,
+            cost,
+        )));
+    }
+
+    let total_cost = if stats.has_unpriced_models {
+        format!("{}+", format_cost(stats.total_cost))
+    } else {
+        format_cost(stats.total_cost)
+    };
+    let total_responses: usize = stats.by_model.iter().map(|m| m.responses).sum();
+    lines.push(Line::from(vec![Span::styled(
+        row(
+            "Total",
+            total_responses.to_string(),
+            &stats.total,
+            total_cost,
+        ),
+        Style::default().add_modifier(Modifier::BOLD),
+    )]));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Token Usage (estimated cost)"),
+    );
+    f.render_widget(paragraph, area);
+}
+
+fn match_style() -> Style {
+    // Underlined as well, so matches stay visible on the selected row
+    Style::default()
+        .bg(Color::Yellow)
+        .fg(Color::Black)
+        .add_modifier(Modifier::UNDERLINED)
+}
+
+fn current_match_style() -> Style {
+    Style::default()
+        .bg(Color::LightRed)
+        .fg(Color::Black)
+        .add_modifier(Modifier::BOLD)
+}
+
+/// Patch the styles of the given byte ranges of a line, splitting its spans
+/// where the ranges start and end.
+fn highlight_line(mut line: Line<'static>, highlights: &[(Range<usize>, Style)]) -> Line<'static> {
+    if highlights.is_empty() {
+        return line;
     }
+
+    let mut spans = Vec::with_capacity(line.spans.len() + highlights.len() * 2);
+    let mut span_start = 0;
+    for span in std::mem::take(&mut line.spans) {
+        let text = span.content.as_ref();
+        let span_end = span_start + text.len();
+        let mut position = span_start;
+        for (range, style) in highlights
+            .iter()
+            .filter(|(range, _)| range.start < span_end && range.end > span_start)
+        {
+            let start = range.start.max(position);
+            let end = range.end.min(span_end);
+            if start > position {
+                spans.push(Span::styled(
+                    text[position - span_start..start - span_start].to_string(),
+                    span.style,
+                ));
+            }
+            spans.push(Span::styled(
+                text[start - span_start..end - span_start].to_string(),
+                span.style.patch(*style),
+            ));
+            position = end;
+        }
+        if position < span_end {
+            spans.push(Span::styled(
+                text[position - span_start..].to_string(),
+                span.style,
+            ));
+        }
+        span_start = span_end;
+    }
+    line.spans = spans;
+    line
+}
+
+/// Search match highlights for `shown`, a copy of `full` possibly truncated
+/// with "..." at either end, placed at byte `offset` of its list row.
+fn truncated_highlights(
+    matcher: Option<&Matcher>,
+    full: &str,
+    shown: &str,
+    offset: usize,
+) -> Vec<(Range<usize>, Style)> {
+    let Some(matcher) = matcher else {
+        return Vec::new();
+    };
+    let ranges = matcher.highlights(full);
+
+    let visible: Vec<Range<usize>> = if shown == full {
+        ranges
+    } else if let Some(kept) = shown
+        .strip_suffix("...")
+        .filter(|kept| full.starts_with(kept))
+    {
+        ranges
+            .into_iter()
+            .filter(|range| range.start < kept.len())
+            .map(|range| range.start..range.end.min(kept.len()))
+            .collect()
+    } else if let Some(kept) = shown
+        .strip_prefix("...")
+        .filter(|kept| full.ends_with(kept))
+    {
+        let skipped = full.len() - kept.len();
+        ranges
+            .into_iter()
+            .filter(|range| range.end > skipped)
+            .map(|range| range.start.max(skipped) - skipped + 3..range.end - skipped + 3)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    visible
+        .into_iter()
+        .map(|range| (range.start + offset..range.end + offset, match_style()))
+        .collect()
 }
 
 fn truncate_string(s: &str, max_len: usize) -> String {
@@ -0,0 +1,220 @@
+use crate::matcher::find_ignoring_case;
+use ratatui::widgets::{Paragraph, Wrap};
+
+const TAB_WIDTH: usize = 4;
+
+/// A search match, as a byte range within one line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewerMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Full-screen, scrollable view of a message's content with its own search.
+///
+/// Lines are wrapped to the terminal width, so scrolling counts wrapped rows
+/// rather than lines. The row layout is set by the UI on every render.
+#[derive(Debug, Default)]
+pub struct Viewer {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: usize, // First visible row
+    pub height: usize, // Visible rows
+    pub search_mode: bool,
+    pub search_query: String,
+    pub matches: Vec<ViewerMatch>,
+    pub current_match: Option<usize>,
+    width: usize,
+    row_starts: Vec<usize>, // Row at which each line starts
+    row_count: usize,
+}
+
+impl Viewer {
+    pub fn new(title: String, content: &str) -> Self {
+        Self {
+            title,
+            lines: content
+                .lines()
+                .map(|line| line.replace('\t', &" ".repeat(TAB_WIDTH)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Record the size of the text area, recomputing wrapped rows when the
+    /// width changed.
+    pub fn set_layout(&mut self, width: usize, height: usize) {
+        if width != self.width || self.row_starts.len() != self.lines.len() {
+            self.width = width;
+            self.row_starts.clear();
+            let mut row = 0;
+            for line in &self.lines {
+                self.row_starts.push(row);
+                row += Paragraph::new(line.as_str())
+                    .wrap(Wrap { trim: false })
+                    .line_count(width as u16)
+                    .max(1);
+            }
+            self.row_count = row;
+        }
+        self.height = height;
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    /// Number of wrapped rows at the current width.
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.row_count.saturating_sub(self.height)
+    }
+
+    fn row_of_line(&self, line: usize) -> usize {
+        // Before the first render every line counts as one row
+        self.row_starts.get(line).copied().unwrap_or(line)
+    }
+
+    /// Index of the first line shown at the top of the view.
+    fn top_line(&self) -> usize {
+        self.row_starts
+            .partition_point(|&row| row <= self.scroll)
+            .saturating_sub(1)
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll = (self.scroll + rows).min(self.max_scroll());
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.height.max(1));
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.height.max(1));
+    }
+
+    pub fn go_to_top(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn go_to_bottom(&mut self) {
+        self.scroll = self.max_scroll();
+    }
+
+    pub fn enter_search_mode(&mut self) {
+        self.search_mode = true;
+        self.search_query.clear();
+        self.update_matches();
+    }
+
+    /// Stop typing the query, keeping its matches highlighted.
+    pub fn confirm_search(&mut self) {
+        self.search_mode = false;
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.search_mode = false;
+        self.search_query.clear();
+        self.update_matches();
+    }
+
+    pub fn add_to_search_query(&mut self, c: char) {
+        self.search_query.push(c);
+        self.update_matches();
+    }
+
+    pub fn remove_from_search_query(&mut self) {
+        self.search_query.pop();
+        self.update_matches();
+    }
+
+    /// Find all occurrences of the query (case-insensitive) and jump to the
+    /// first one at or after the top of the view.
+    fn update_matches(&mut self) {
+        self.matches.clear();
+        self.current_match = None;
+        if self.search_query.is_empty() {
+            return;
+        }
+
+        for (line_index, line) in self.lines.iter().enumerate() {
+            for range in find_ignoring_case(line, &self.search_query) {
+                self.matches.push(ViewerMatch {
+                    line: line_index,
+                    start: range.start,
+                    end: range.end,
+                });
+            }
+        }
+
+        let top_line = self.top_line();
+        let first = self
+            .matches
+            .iter()
+            .position(|m| m.line >= top_line)
+            .or_else(|| (!self.matches.is_empty()).then_some(0));
+        if let Some(index) = first {
+            self.jump_to_match(index);
+        }
+    }
+
+    pub fn next_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let next = match self.current_match {
+            Some(current) => (current + 1) % self.matches.len(),
+            None => 0,
+        };
+        self.jump_to_match(next);
+    }
+
+    pub fn previous_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let previous = match self.current_match {
+            Some(0) | None => self.matches.len() - 1,
+            Some(current) => current - 1,
+        };
+        self.jump_to_match(previous);
+    }
+
+    /// Make a match current, scrolling it into view when it isn't visible.
+    fn jump_to_match(&mut self, index: usize) {
+        self.current_match = Some(index);
+        let row = self.row_of_line(self.matches[index].line);
+        if row < self.scroll || row >= self.scroll + self.height.max(1) {
+            // Leave some context above the match
+            self.scroll = row.saturating_sub(self.height / 3);
+            if !self.row_starts.is_empty() {
+                self.scroll = self.scroll.min(self.max_scroll());
+            }
+        }
+    }
+
+    /// Status line text describing the search state.
+    pub fn search_status(&self) -> Option<String> {
+        if self.search_mode {
+            return Some(format!("/{}", self.search_query));
+        }
+        if self.search_query.is_empty() {
+            return None;
+        }
+        Some(match self.current_match {
+            Some(current) => format!(
+                "/{}: match {}/{}",
+                self.search_query,
+                current + 1,
+                self.matches.len()
+            ),
+            None => format!("Pattern not found: {}", self.search_query),
+        })
+    }
+}